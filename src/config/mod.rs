@@ -0,0 +1,7 @@
+pub mod job;
+
+pub use job::{
+    AdaptiveConfig, BackendConfig, BreakerAction, BrowserConfig, BrowserProfile,
+    CircuitBreakerConfig, GeocoderConfig, GridSpec, HttpConfig, HttpVersion, Job, JobFile, Locale,
+    OutputSink, PlacesEndpoint, ProxyConfig, ProxyRotation, RateLimit, RetryPolicy,
+};
@@ -1,3 +1,5 @@
+use reqwest::StatusCode;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -5,6 +7,24 @@ pub enum AppError {
     #[error("HTTP request failed: {0}")]
     HttpError(#[from] reqwest::Error),
 
+    #[error("HTTP request returned status {status}")]
+    HttpStatus {
+        status: u16,
+        retry_after: Option<Duration>,
+    },
+
+    #[error("Google answered with a consent page instead of results: {0}")]
+    ConsentPage(String),
+
+    #[error("Google answered with a CAPTCHA page, requests are being blocked: {0}")]
+    CaptchaPage(String),
+
+    #[error("Google answered with an unexpected HTML page: {0}")]
+    UnexpectedHtml(String),
+
+    #[error("Circuit breaker open, run aborted: {0}")]
+    CircuitOpen(String),
+
     #[error("JSON parsing failed: {0}")]
     JsonError(#[from] serde_json::Error),
 
@@ -25,4 +45,115 @@ pub enum AppError {
 
     #[error("Failed to extract value from JSON")]
     ValueExtractionFailed,
+
+    #[error("TOML parsing failed: {0}")]
+    TomlError(#[from] toml::de::Error),
+
+    #[error("YAML parsing failed: {0}")]
+    YamlError(#[from] serde_yaml::Error),
+
+    #[error("Unsupported job file, expected .toml, .yaml or .yml: {0}")]
+    UnsupportedJobFile(String),
+
+    #[error("Invalid job '{job}': {reason}")]
+    InvalidJob { job: String, reason: String },
+
+    #[error("Invalid grid: {0}")]
+    InvalidGrid(String),
+
+    #[error("Invalid polygon: {0}")]
+    InvalidPolygon(String),
+
+    #[error("Invalid URL: {0}")]
+    UrlError(#[from] url::ParseError),
+
+    #[error("GOOGLE_MAPS_API_KEY is not set, set it or choose a keyless geocoder")]
+    MissingApiKey,
+
+    #[error("No results found for '{0}'")]
+    ZeroResults(String),
+
+    #[error("Places API quota exceeded: {0}")]
+    OverQueryLimit(String),
+
+    #[error("Places API request denied: {0}")]
+    RequestDenied(String),
+
+    #[error("Invalid Places API request: {0}")]
+    InvalidRequest(String),
+
+    #[error("Places API server error: {0}")]
+    PlacesServerError(String),
+
+    #[error("Unexpected Places API status: {0}")]
+    UnexpectedStatus(String),
+
+    #[error("Area not found in the gazetteer: {0}")]
+    AreaNotFound(String),
+
+    #[error("Area '{area}' exists in several countries ({countries}), add a country code")]
+    AmbiguousArea { area: String, countries: String },
+
+    #[error("Invalid gazetteer: {0}")]
+    InvalidGazetteer(String),
+
+    #[error("Invalid pb parameter: {0}")]
+    InvalidPb(String),
+
+    #[error("Invalid scraper configuration: {0}")]
+    InvalidScraper(String),
+
+    #[error("Invalid proxy: {0}")]
+    InvalidProxy(String),
+
+    #[error("Every proxy has been evicted after repeated failures")]
+    NoProxyAvailable,
+
+    #[error("Invalid header '{header}' in browser profile '{profile}'")]
+    InvalidHeader { profile: String, header: String },
+
+    #[error("Unknown browser profile: {0}")]
+    UnknownProfile(String),
+
+    #[error("Session bootstrap failed: {0}")]
+    SessionBootstrap(String),
+
+    #[error("Invalid CA bundle {path}: {reason}")]
+    InvalidCaBundle { path: String, reason: String },
+
+    #[error("Invalid value for environment variable {name}: {value}")]
+    InvalidEnvVar { name: String, value: String },
+}
+
+impl AppError {
+    // Failures that may succeed when repeated: rate limiting, server errors,
+    // timeouts and broken connections.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            AppError::HttpStatus { status, .. } => {
+                *status == StatusCode::TOO_MANY_REQUESTS.as_u16() || *status >= 500
+            }
+            AppError::HttpError(e) => {
+                e.is_timeout() || e.is_connect() || e.is_request() || e.is_body()
+            }
+            AppError::OverQueryLimit(_) | AppError::PlacesServerError(_) => true,
+            _ => false,
+        }
+    }
+
+    // Wait requested by the server through `Retry-After`.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            AppError::HttpStatus { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    // Responses showing that Google is refusing to serve results.
+    pub fn is_block(&self) -> bool {
+        matches!(
+            self,
+            AppError::CaptchaPage(_) | AppError::ConsentPage(_) | AppError::UnexpectedHtml(_)
+        )
+    }
 }
@@ -1,111 +1,169 @@
+pub mod cli;
+pub mod config;
 pub mod error;
 pub mod models;
+pub mod scraper;
 pub mod services;
 pub mod utils;
 
+pub use config::{Job, JobFile};
+pub use scraper::{ScrapeReport, Scraper, ScraperBuilder};
+
+use config::{GeocoderConfig, GridSpec, HttpConfig, RetryPolicy};
 use error::AppError;
-use models::coordinates::Coordinates;
-use models::place::Place;
+use models::polygon::MultiPolygon;
 use models::viewport::Viewport;
-use services::google_maps::{build_url, fetch_and_process_data, search_location};
-use services::grid_generator::generate_grid;
-use utils::json_utils::save_places;
+use services::grid_generator::{clip_cells, generate_grid_cells};
+use services::{search_backend, viewport_provider};
+use utils::export::{save_places_as, save_plan};
+use utils::http_fetcher::{HttpFetcher, ReqwestFetcher};
 use utils::logger::init_logger;
+use utils::proxy_pool::ProxyPool;
 
-use dotenvy::dotenv;
-use futures::future::join_all;
-use std::collections::HashSet;
-use std::env;
-use std::sync::{Arc, Mutex};
-use tokio::time::{sleep, Duration};
+use std::path::Path;
+use std::sync::Arc;
 
 pub async fn run() -> Result<(), AppError> {
     init_logger();
-    // Load .env file
-    dotenv().ok();
-    let api_key = env::var("GOOGLE_MAPS_API_KEY").expect("API key not found");
-    let grid_query = "Karachi";
-    let query = "restraunts north nazimabad";
-    let start = 200;
+    run_with(&Job::default()).await
+}
+
+// Loads a job file and runs its jobs one after another.
+pub async fn run_job_file(path: &Path) -> Result<(), AppError> {
+    let job_file = JobFile::load(path)?;
+    log::info!(
+        "Loaded {} jobs from {}",
+        job_file.jobs.len(),
+        path.display()
+    );
+    for job in &job_file.jobs {
+        log::info!("Running job '{}'", job.name);
+        run_with(job).await?;
+    }
+    Ok(())
+}
+
+// Resolves an area name to its viewport through the configured geocoder.
+pub async fn geocode(
+    area: &str,
+    geocoder: &GeocoderConfig,
+    http: &HttpConfig,
+) -> Result<Viewport, AppError> {
+    let fetcher = Arc::new(ReqwestFetcher::from_config(http)?);
+    resolve_viewport(area, geocoder, fetcher, &RetryPolicy::default()).await
+}
 
-    log::info!("Starting search for location: {}", grid_query);
-    let result = search_location(&api_key, grid_query).await?;
-    // Extract the viewport from the first result
-    log::info!("Extracting viewport from search results");
-    let viewport = Viewport::extract_viewport(&result)?;
+async fn resolve_viewport(
+    area: &str,
+    geocoder: &GeocoderConfig,
+    fetcher: Arc<dyn HttpFetcher>,
+    retry: &RetryPolicy,
+) -> Result<Viewport, AppError> {
+    let provider = viewport_provider::from_config(geocoder, fetcher, retry)?;
+    let viewport = provider.viewport(area).await?;
     log::info!("Northeast: {:?}", viewport.northeast);
     log::info!("Southwest: {:?}", viewport.southwest);
-    // Generate a 15x15 grid
-    log::info!("Generating 15x15 grid");
-    let grid = generate_grid(&viewport, 15, 15);
-    crate::debug_log!("15x15 Grid: {:?}", grid);
-
-    log::info!("Initializing HTTP client and shared state");
-    let client = reqwest::Client::new();
+    Ok(viewport)
+}
 
-    // Create a shared HashSet to track unique place_ids
-    let unique_places: Arc<Mutex<HashSet<String>>> = Arc::new(Mutex::new(HashSet::new()));
+// Resolves an area and returns the centres of the grid cells laid over its
+// viewport, or over the bounding box of a boundary file when one is given.
+pub async fn grid(
+    area: &str,
+    geocoder: &GeocoderConfig,
+    boundary: Option<&Path>,
+    spec: &GridSpec,
+    http: &HttpConfig,
+) -> Result<Vec<(f64, f64)>, AppError> {
+    spec.validate()?;
+    let cells = match boundary {
+        Some(path) => {
+            let polygon = MultiPolygon::load(path)?;
+            clip_cells(generate_grid_cells(&polygon.viewport(), spec), &polygon)
+        }
+        None => generate_grid_cells(&geocode(area, geocoder, http).await?, spec),
+    };
+    match spec.cell_size_m {
+        Some(size) => log::info!("Generated {} cells of {} m", cells.len(), size),
+        None => log::info!("Generated {}x{} grid", spec.rows, spec.cols),
+    }
+    crate::debug_log!("Grid cells: {:?}", cells);
 
-    // Create a shared vector to store all unique places
-    let all_places: Arc<Mutex<Vec<Place>>> = Arc::new(Mutex::new(Vec::new()));
+    Ok(cells.iter().map(|cell| cell.center()).collect())
+}
 
-    // Flatten the grid into a single vector of (lat, long) tuples
-    let grid_points: Vec<(f64, f64)> = grid.into_iter().flatten().collect();
+// Runs a job through the `Scraper` and writes the places to every output of the job.
+pub async fn run_with(job: &Job) -> Result<(), AppError> {
+    let fetcher = Arc::new(ReqwestFetcher::from_config(&job.http)?);
+    run_with_fetcher(job, fetcher).await
+}
 
-    // Process grid points in parallel with a rate limit of 5 requests per second
-    log::info!("Processing {} grid points in parallel", grid_points.len());
-    for chunk in grid_points.chunks(10) {
-        let mut chunk_tasks = Vec::new();
-        for &(lat, long) in chunk {
-            let url = build_url(lat, long, start, query);
-            let client = client.clone();
-            let unique_places = Arc::clone(&unique_places);
-            let all_places = Arc::clone(&all_places);
-            let searched_coords = Coordinates {
-                lat: Some(lat),
-                long: Some(long),
-            };
-            let task = tokio::spawn(async move {
-                match fetch_and_process_data(
-                    &client,
-                    &url,
-                    &unique_places,
-                    &all_places,
-                    searched_coords,
-                )
-                .await
-                {
-                    Ok(_) => log::info!(
-                        "Successfully processed data for lat: {}, long: {}",
-                        lat,
-                        long
-                    ),
-                    Err(e) => crate::error_log!(
-                        "Error processing data for lat: {}, long: {}: {}",
-                        lat,
-                        long,
-                        e
-                    ),
-                }
-            });
-            chunk_tasks.push(task);
+// Runs a job with every request sent through `fetcher`, so a `FixtureFetcher`
+// runs it offline.
+pub async fn run_with_fetcher(job: &Job, fetcher: Arc<dyn HttpFetcher>) -> Result<(), AppError> {
+    let proxies = match &job.proxies {
+        Some(config) => Some(Arc::new(ProxyPool::from_config(config, fetcher.as_ref())?)),
+        None => None,
+    };
+    let backend = search_backend::from_config(
+        &job.backend,
+        Arc::clone(&fetcher),
+        &job.locale,
+        job.start,
+        proxies,
+        &job.browser,
+    )?;
+    let builder = ScraperBuilder::from_job(job).backend(backend);
+    let builder = match &job.boundary {
+        Some(path) => builder.polygon(MultiPolygon::load(path)?),
+        None => {
+            builder.viewport(resolve_viewport(&job.area, &job.geocoder, fetcher, &job.retry).await?)
         }
-        // Wait for all tasks in the current chunk to complete
-        log::info!("Waiting for tasks in the current chunk to complete");
-        join_all(chunk_tasks).await;
-        // Sleep for 1 second to enforce the rate limit
-        log::info!("Sleeping for 1 second to enforce rate limit");
-        sleep(Duration::from_secs(1)).await;
+    };
+    let scraper = builder.build()?;
+    let report = scraper.run().await?;
+    log::info!(
+        "Sent {} requests for {} grid points, {} of them retries, {} pages failed, {} blocked",
+        report.requests,
+        report.grid_points,
+        report.retries,
+        report.failed_requests,
+        report.blocked_requests
+    );
+    for proxy in &report.proxies {
+        log::info!(
+            "Proxy {}: {} requests, {} succeeded, {} failed ({} blocked){}",
+            proxy.proxy,
+            proxy.requests,
+            proxy.successes,
+            proxy.failures,
+            proxy.blocks,
+            if proxy.evicted { ", evicted" } else { "" }
+        );
     }
 
-    log::info!("Saving all unique places to JSON file"); // Save all unique places to a single JSON file
-    let all_places = all_places.lock().unwrap();
-    save_places(&*all_places)?;
+    if let Some(path) = &job.plan_output {
+        log::info!(
+            "Saving the plan of {} cells to {}",
+            report.plan.len(),
+            path.display()
+        );
+        save_plan(&report.plan, path)?;
+    }
 
-    log::info!(
-        "Exported {} unique places to all_places_output.json",
-        all_places.len()
-    );
+    for output in &job.outputs {
+        log::info!("Saving all unique places to {}", output.path.display());
+        save_places_as(&report.places, &output.path, output.format())?;
+        log::info!(
+            "Exported {} unique places to {}",
+            report.places.len(),
+            output.path.display()
+        );
+    }
 
-    Ok(())
+    // The places found before the circuit breaker opened are saved all the same
+    match report.aborted {
+        Some(reason) => Err(AppError::CircuitOpen(reason)),
+        None => Ok(()),
+    }
 }
@@ -1,6 +1,169 @@
-use google_maps::run;
+use clap::Parser;
+use google_maps::cli::{Cli, Command};
+use google_maps::config::{
+    AdaptiveConfig, BrowserConfig, CircuitBreakerConfig, Locale, OutputSink, ProxyConfig,
+    RateLimit, RetryPolicy,
+};
+use google_maps::error::AppError;
+use google_maps::models::MultiPolygon;
+use google_maps::services::grid_generator::compare_tilings;
+use google_maps::services::pb_decoder::{diff_fields, format_tree, parse_input, template_like};
+use google_maps::utils::export::save_places_as;
+use google_maps::utils::json_utils::load_places;
+use google_maps::utils::logger::init_logger;
+use google_maps::{geocode, grid, run_job_file, run_with, Job, JobFile};
+use std::fs;
+use std::path::Path;
 
 #[tokio::main]
-async fn main() -> Result<(), google_maps::error::AppError> {
-    run().await
+async fn main() -> Result<(), AppError> {
+    let cli = Cli::parse();
+    init_logger();
+
+    match cli.command {
+        Command::Scrape(args) => {
+            let job = Job {
+                grid: args.grid.spec(),
+                geocoder: args.grid.area.geocoder(),
+                backend: args.backend(),
+                area: args.grid.area.area,
+                boundary: args.grid.boundary,
+                clip_places: args.clip_places,
+                queries: args.queries,
+                start: args.start,
+                max_pages: args.max_pages,
+                rate_limit: RateLimit {
+                    concurrency: args.concurrency,
+                    requests_per_second: args.requests_per_second,
+                    burst: args.burst,
+                },
+                locale: Locale {
+                    language: args.language,
+                    region: args.region,
+                },
+                retry: RetryPolicy {
+                    max_attempts: args.max_attempts,
+                    ..RetryPolicy::default()
+                },
+                circuit_breaker: CircuitBreakerConfig {
+                    action: args.on_block,
+                    ..CircuitBreakerConfig::default()
+                },
+                proxies: args.proxies.map(|file| ProxyConfig {
+                    file: Some(file),
+                    rotation: args.proxy_rotation,
+                    ..ProxyConfig::default()
+                }),
+                browser: BrowserConfig {
+                    profiles: args.profiles,
+                    bootstrap: !args.no_bootstrap,
+                    ..BrowserConfig::default()
+                },
+                http: args.grid.area.http.config(),
+                outputs: vec![OutputSink {
+                    path: args.output,
+                    format: Some(args.format),
+                }],
+                adaptive: args.adaptive.then(|| AdaptiveConfig {
+                    max_depth: args.max_depth,
+                    min_cell_size_m: args.min_cell_size,
+                    ..AdaptiveConfig::default()
+                }),
+                plan_output: args.plan,
+                ..Job::default()
+            };
+            job.validate()?;
+            run_with(&job).await
+        }
+        Command::Geocode(args) => {
+            let viewport = geocode(&args.area, &args.geocoder(), &args.http.config()).await?;
+            println!("{}", serde_json::to_string_pretty(&viewport)?);
+            Ok(())
+        }
+        Command::Grid(args) if args.stats => {
+            let spec = args.grid.spec();
+            spec.validate()?;
+            let viewport = match &args.grid.boundary {
+                Some(path) => MultiPolygon::load(path)?.viewport(),
+                None => {
+                    geocode(
+                        &args.grid.area.area,
+                        &args.grid.area.geocoder(),
+                        &args.grid.area.http.config(),
+                    )
+                    .await?
+                }
+            };
+            let radius = args.grid.radius.unwrap_or_default();
+            let stats = compare_tilings(&viewport, &spec, radius)?;
+            println!("{}", serde_json::to_string_pretty(&stats)?);
+            Ok(())
+        }
+        Command::Grid(args) => {
+            let points = grid(
+                &args.grid.area.area,
+                &args.grid.area.geocoder(),
+                args.grid.boundary.as_deref(),
+                &args.grid.spec(),
+                &args.grid.area.http.config(),
+            )
+            .await?;
+            for (lat, long) in &points {
+                println!("{},{}", lat, long);
+            }
+            log::info!(
+                "{} grid points, one request per point and query",
+                points.len()
+            );
+            Ok(())
+        }
+        Command::Export(args) => {
+            let places = load_places(&args.input)?;
+            save_places_as(&places, &args.output, args.format)?;
+            log::info!(
+                "Exported {} places to {}",
+                places.len(),
+                args.output.display()
+            );
+            Ok(())
+        }
+        Command::Jobs(args) if args.check => {
+            let job_file = JobFile::load(&args.file)?;
+            log::info!(
+                "{} is valid and defines {} jobs",
+                args.file.display(),
+                job_file.jobs.len()
+            );
+            Ok(())
+        }
+        Command::Jobs(args) => run_job_file(&args.file).await,
+        Command::Pb(args) => {
+            let fields = parse_input(&read_input(&args.input)?)?;
+            let other = match (&args.diff, args.template) {
+                (Some(other), _) => Some(parse_input(&read_input(other)?)?),
+                (None, true) => Some(template_like(&fields)),
+                (None, false) => None,
+            };
+            match other {
+                Some(other) => {
+                    let changes = diff_fields(&fields, &other);
+                    for change in &changes {
+                        println!("{}", change);
+                    }
+                    log::info!("{} fields differ", changes.len());
+                }
+                None => print!("{}", format_tree(&fields)),
+            }
+            Ok(())
+        }
+    }
+}
+
+// Command line values naming an existing file are replaced by its content.
+fn read_input(value: &str) -> Result<String, AppError> {
+    if Path::new(value).is_file() {
+        Ok(fs::read_to_string(value)?)
+    } else {
+        Ok(value.to_string())
+    }
 }
@@ -1,9 +1,9 @@
 use crate::utils::json_utils::{extract_value, get_nested_value};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Address {
     pub street_address: Option<String>,
     pub city: Option<String>,
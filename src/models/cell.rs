@@ -0,0 +1,68 @@
+use serde::Serialize;
+
+// Metres per degree of latitude, also per degree of longitude at the equator
+pub const METRES_PER_DEGREE: f64 = 111_320.0;
+
+// A rectangular search cell, described by its centre and its size in degrees.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Cell {
+    pub lat: f64,
+    pub lng: f64,
+    pub lat_span: f64,
+    pub lng_span: f64,
+    // Number of subdivisions from the initial grid cell
+    pub depth: u32,
+}
+
+impl Cell {
+    pub fn new(lat: f64, lng: f64, lat_span: f64, lng_span: f64) -> Self {
+        Cell {
+            lat,
+            lng,
+            lat_span,
+            lng_span,
+            depth: 0,
+        }
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        (self.lat, self.lng)
+    }
+
+    // (south, west, north, east) edges of the cell.
+    pub fn bounds(&self) -> (f64, f64, f64, f64) {
+        (
+            self.lat - self.lat_span / 2.0,
+            self.lng - self.lng_span / 2.0,
+            self.lat + self.lat_span / 2.0,
+            self.lng + self.lng_span / 2.0,
+        )
+    }
+
+    // Height and width of the cell in metres.
+    pub fn size_m(&self) -> (f64, f64) {
+        let height = self.lat_span * METRES_PER_DEGREE;
+        let width = self.lng_span * METRES_PER_DEGREE * self.lat.to_radians().cos();
+        (height, width)
+    }
+
+    // Splits the cell into its four quadrants, one level deeper.
+    pub fn split(&self) -> [Cell; 4] {
+        let lat_span = self.lat_span / 2.0;
+        let lng_span = self.lng_span / 2.0;
+        let quadrant = |lat_sign: f64, lng_sign: f64| Cell {
+            lat: self.lat + lat_sign * lat_span / 2.0,
+            lng: self.lng + lng_sign * lng_span / 2.0,
+            lat_span,
+            lng_span,
+            depth: self.depth + 1,
+        };
+
+        [
+            quadrant(-1.0, -1.0),
+            quadrant(-1.0, 1.0),
+            quadrant(1.0, -1.0),
+            quadrant(1.0, 1.0),
+        ]
+    }
+}
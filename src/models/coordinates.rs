@@ -1,9 +1,9 @@
 use crate::utils::json_utils::{extract_value, get_nested_value};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Coordinates {
     pub lat: Option<f64>,
     pub long: Option<f64>,
@@ -1,9 +1,15 @@
 pub mod address;
+pub mod cell;
 pub mod coordinates;
 pub mod place;
+pub mod polygon;
+pub mod text_search;
 pub mod viewport;
 
 pub use address::Address;
+pub use cell::Cell;
 pub use coordinates::Coordinates;
 pub use place::Place;
+pub use polygon::MultiPolygon;
+pub use text_search::TextSearchResponse;
 pub use viewport::Viewport;
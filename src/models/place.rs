@@ -1,7 +1,7 @@
 use super::{Address, Coordinates};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Place {
     pub address: Address,
     pub name: Option<String>,
@@ -18,4 +18,7 @@ pub struct Place {
     pub online_delivery_link: Option<String>,
     pub coordinates: Coordinates,
     pub searched_coords: Coordinates,
+    // Result offset of the page the place was found on
+    #[serde(default)]
+    pub page_offset: Option<u32>,
 }
@@ -0,0 +1,317 @@
+use crate::error::AppError;
+use crate::models::cell::Cell;
+use crate::models::viewport::Viewport;
+
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+// A ring of (lat, lng) vertices, implicitly closed.
+pub type Ring = Vec<(f64, f64)>;
+
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    pub exterior: Ring,
+    pub holes: Vec<Ring>,
+}
+
+// One or more polygons describing a search area, such as an administrative boundary.
+#[derive(Debug, Clone)]
+pub struct MultiPolygon {
+    pub polygons: Vec<Polygon>,
+}
+
+impl MultiPolygon {
+    // Loads a GeoJSON or WKT file, detected from its content.
+    pub fn load(path: &Path) -> Result<Self, AppError> {
+        let raw = fs::read_to_string(path)?;
+        if raw.trim_start().starts_with('{') {
+            Self::from_geojson(&serde_json::from_str(&raw)?)
+        } else {
+            Self::from_wkt(&raw)
+        }
+    }
+
+    // Reads every Polygon and MultiPolygon of a geometry, Feature or FeatureCollection.
+    pub fn from_geojson(json: &Value) -> Result<Self, AppError> {
+        let mut polygons = Vec::new();
+        collect_geojson(json, &mut polygons)?;
+        Self::new(polygons)
+    }
+
+    // Reads a WKT POLYGON or MULTIPOLYGON.
+    pub fn from_wkt(wkt: &str) -> Result<Self, AppError> {
+        let wkt = wkt.trim();
+        let split = wkt
+            .find('(')
+            .ok_or_else(|| invalid("WKT geometry has no coordinates"))?;
+        let (kind, body) = wkt.split_at(split);
+
+        let tree = parse_wkt_group(&mut body.chars().filter(|c| !c.is_control()).peekable())?;
+        let polygons = match kind.trim().to_ascii_uppercase().as_str() {
+            "POLYGON" => vec![wkt_polygon(&tree)?],
+            "MULTIPOLYGON" => match &tree {
+                WktNode::Group(polygons) => {
+                    polygons.iter().map(wkt_polygon).collect::<Result<_, _>>()?
+                }
+                WktNode::Coords(_) => return Err(invalid("MULTIPOLYGON must contain polygons")),
+            },
+            other => return Err(invalid(&format!("unsupported WKT geometry {}", other))),
+        };
+        Self::new(polygons)
+    }
+
+    fn new(polygons: Vec<Polygon>) -> Result<Self, AppError> {
+        if polygons.is_empty() {
+            return Err(invalid("no polygon found"));
+        }
+        if polygons.iter().any(|p| p.exterior.len() < 3) {
+            return Err(invalid("polygon rings need at least three vertices"));
+        }
+        Ok(MultiPolygon { polygons })
+    }
+
+    // Bounding box of all polygons.
+    pub fn viewport(&self) -> Viewport {
+        let vertices = self.polygons.iter().flat_map(|p| p.exterior.iter());
+        let (mut south, mut west) = (f64::MAX, f64::MAX);
+        let (mut north, mut east) = (f64::MIN, f64::MIN);
+        for &(lat, lng) in vertices {
+            south = south.min(lat);
+            north = north.max(lat);
+            west = west.min(lng);
+            east = east.max(lng);
+        }
+        Viewport {
+            northeast: (north, east),
+            southwest: (south, west),
+        }
+    }
+
+    pub fn contains(&self, lat: f64, lng: f64) -> bool {
+        self.polygons.iter().any(|p| p.contains(lat, lng))
+    }
+
+    // Whether the cell and the area share any point.
+    pub fn intersects_cell(&self, cell: &Cell) -> bool {
+        self.polygons.iter().any(|p| p.intersects_cell(cell))
+    }
+}
+
+impl Polygon {
+    pub fn contains(&self, lat: f64, lng: f64) -> bool {
+        ring_contains(&self.exterior, lat, lng)
+            && !self.holes.iter().any(|hole| ring_contains(hole, lat, lng))
+    }
+
+    pub fn intersects_cell(&self, cell: &Cell) -> bool {
+        let (south, west, north, east) = cell.bounds();
+        let corners = [(south, west), (south, east), (north, east), (north, west)];
+
+        // The cell lies in the polygon, or the polygon lies in the cell
+        if corners.iter().any(|&(lat, lng)| self.contains(lat, lng)) {
+            return true;
+        }
+        let in_cell = |&(lat, lng): &(f64, f64)| {
+            (south..=north).contains(&lat) && (west..=east).contains(&lng)
+        };
+        if self.exterior.iter().any(in_cell) {
+            return true;
+        }
+
+        // Otherwise their boundaries must cross
+        let cell_edges: Vec<_> = (0..4).map(|i| (corners[i], corners[(i + 1) % 4])).collect();
+        std::iter::once(&self.exterior)
+            .chain(&self.holes)
+            .flat_map(ring_edges)
+            .any(|edge| {
+                cell_edges
+                    .iter()
+                    .any(|&cell_edge| segments_cross(edge, cell_edge))
+            })
+    }
+}
+
+type Segment = ((f64, f64), (f64, f64));
+
+fn ring_edges(ring: &Ring) -> impl Iterator<Item = Segment> + '_ {
+    (0..ring.len()).map(move |i| (ring[i], ring[(i + 1) % ring.len()]))
+}
+
+// Even-odd ray casting along the longitude axis.
+fn ring_contains(ring: &Ring, lat: f64, lng: f64) -> bool {
+    let mut inside = false;
+    for ((lat_a, lng_a), (lat_b, lng_b)) in ring_edges(ring) {
+        if (lat_a > lat) != (lat_b > lat) {
+            let crossing = lng_a + (lat - lat_a) / (lat_b - lat_a) * (lng_b - lng_a);
+            if lng < crossing {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+fn segments_cross((a, b): Segment, (c, d): Segment) -> bool {
+    let orientation = |p: (f64, f64), q: (f64, f64), r: (f64, f64)| {
+        ((q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)).signum()
+    };
+    let (o1, o2) = (orientation(a, b, c), orientation(a, b, d));
+    let (o3, o4) = (orientation(c, d, a), orientation(c, d, b));
+    o1 != o2 && o3 != o4
+}
+
+fn invalid(reason: &str) -> AppError {
+    AppError::InvalidPolygon(reason.to_string())
+}
+
+fn collect_geojson(json: &Value, polygons: &mut Vec<Polygon>) -> Result<(), AppError> {
+    match json.get("type").and_then(Value::as_str) {
+        Some("FeatureCollection") => {
+            let features = json
+                .get("features")
+                .and_then(Value::as_array)
+                .ok_or_else(|| invalid("FeatureCollection has no features"))?;
+            for feature in features {
+                collect_geojson(feature, polygons)?;
+            }
+        }
+        Some("Feature") => {
+            if let Some(geometry) = json.get("geometry").filter(|g| !g.is_null()) {
+                collect_geojson(geometry, polygons)?;
+            }
+        }
+        Some("GeometryCollection") => {
+            for geometry in json
+                .get("geometries")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                collect_geojson(geometry, polygons)?;
+            }
+        }
+        Some("Polygon") => polygons.push(geojson_polygon(&json["coordinates"])?),
+        Some("MultiPolygon") => {
+            let parts = json["coordinates"]
+                .as_array()
+                .ok_or_else(|| invalid("MultiPolygon coordinates must be an array"))?;
+            for part in parts {
+                polygons.push(geojson_polygon(part)?);
+            }
+        }
+        // Points and lines do not describe an area
+        Some(_) => {}
+        None => return Err(invalid("GeoJSON object has no type")),
+    }
+    Ok(())
+}
+
+// GeoJSON positions are [lng, lat]; the first ring is the exterior.
+fn geojson_polygon(coordinates: &Value) -> Result<Polygon, AppError> {
+    let mut rings = coordinates
+        .as_array()
+        .ok_or_else(|| invalid("Polygon coordinates must be an array"))?
+        .iter()
+        .map(|ring| {
+            ring.as_array()
+                .ok_or_else(|| invalid("Polygon rings must be arrays"))?
+                .iter()
+                .map(|position| match (position.get(0), position.get(1)) {
+                    (Some(lng), Some(lat)) => lat
+                        .as_f64()
+                        .zip(lng.as_f64())
+                        .ok_or_else(|| invalid("positions must be numbers")),
+                    _ => Err(invalid("positions need a longitude and a latitude")),
+                })
+                .collect::<Result<Ring, _>>()
+        })
+        .collect::<Result<Vec<Ring>, _>>()?;
+
+    if rings.is_empty() {
+        return Err(invalid("Polygon has no exterior ring"));
+    }
+    let exterior = rings.remove(0);
+    Ok(Polygon {
+        exterior,
+        holes: rings,
+    })
+}
+
+// Nested parenthesised groups of a WKT body, with coordinate lists as leaves.
+#[derive(Debug)]
+enum WktNode {
+    Group(Vec<WktNode>),
+    Coords(Ring),
+}
+
+fn parse_wkt_group(
+    chars: &mut std::iter::Peekable<impl Iterator<Item = char>>,
+) -> Result<WktNode, AppError> {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+    if chars.next() != Some('(') {
+        return Err(invalid("expected '(' in WKT"));
+    }
+
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+    if chars.peek() == Some(&'(') {
+        let mut children = Vec::new();
+        loop {
+            children.push(parse_wkt_group(chars)?);
+            while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                chars.next();
+            }
+            match chars.next() {
+                Some(',') => continue,
+                Some(')') => return Ok(WktNode::Group(children)),
+                _ => return Err(invalid("unbalanced parentheses in WKT")),
+            }
+        }
+    }
+
+    let mut text = String::new();
+    for c in chars.by_ref() {
+        if c == ')' {
+            return wkt_coords(&text).map(WktNode::Coords);
+        }
+        text.push(c);
+    }
+    Err(invalid("unbalanced parentheses in WKT"))
+}
+
+// WKT positions are "lng lat", separated by commas.
+fn wkt_coords(text: &str) -> Result<Ring, AppError> {
+    text.split(',')
+        .map(|position| {
+            let mut numbers = position.split_whitespace().map(str::parse::<f64>);
+            match (numbers.next(), numbers.next()) {
+                (Some(Ok(lng)), Some(Ok(lat))) => Ok((lat, lng)),
+                _ => Err(invalid(&format!(
+                    "invalid WKT position '{}'",
+                    position.trim()
+                ))),
+            }
+        })
+        .collect()
+}
+
+fn wkt_polygon(node: &WktNode) -> Result<Polygon, AppError> {
+    let WktNode::Group(rings) = node else {
+        return Err(invalid("POLYGON must contain rings"));
+    };
+    let mut rings = rings.iter().map(|ring| match ring {
+        WktNode::Coords(coords) => Ok(coords.clone()),
+        WktNode::Group(_) => Err(invalid("POLYGON rings must be coordinate lists")),
+    });
+    let exterior = rings
+        .next()
+        .ok_or_else(|| invalid("POLYGON has no exterior ring"))??;
+    Ok(Polygon {
+        exterior,
+        holes: rings.collect::<Result<_, _>>()?,
+    })
+}
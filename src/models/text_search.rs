@@ -0,0 +1,83 @@
+use crate::error::AppError;
+use crate::models::viewport::Viewport;
+
+use serde::Deserialize;
+
+// Response of the Places Text Search API, also the shape of Nearby Search responses.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TextSearchResponse {
+    pub status: String,
+    #[serde(default)]
+    pub results: Vec<TextSearchResult>,
+    pub error_message: Option<String>,
+    // Token for the next 20 results, usable a few seconds after this response
+    pub next_page_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TextSearchResult {
+    pub name: Option<String>,
+    pub formatted_address: Option<String>,
+    // Short address returned by Nearby Search instead of `formatted_address`
+    pub vicinity: Option<String>,
+    pub place_id: Option<String>,
+    pub geometry: Geometry,
+    #[serde(default)]
+    pub types: Vec<String>,
+    pub rating: Option<f64>,
+    pub user_ratings_total: Option<u64>,
+    // 0 (free) to 4 (very expensive)
+    pub price_level: Option<u8>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Geometry {
+    pub location: LatLng,
+    pub viewport: Option<Bounds>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct LatLng {
+    pub lat: f64,
+    pub lng: f64,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Bounds {
+    pub northeast: LatLng,
+    pub southwest: LatLng,
+}
+
+impl TextSearchResponse {
+    // Turns every status but OK into its error.
+    pub fn check_status(self, query: &str) -> Result<Self, AppError> {
+        let message = self.error_message.clone().unwrap_or_default();
+        match self.status.as_str() {
+            "OK" => Ok(self),
+            "ZERO_RESULTS" => Err(AppError::ZeroResults(query.to_string())),
+            "OVER_QUERY_LIMIT" => Err(AppError::OverQueryLimit(message)),
+            "REQUEST_DENIED" => Err(AppError::RequestDenied(message)),
+            "INVALID_REQUEST" => Err(AppError::InvalidRequest(message)),
+            "UNKNOWN_ERROR" => Err(AppError::PlacesServerError(message)),
+            status => Err(AppError::UnexpectedStatus(status.to_string())),
+        }
+    }
+
+    // Viewport of the first result.
+    pub fn viewport(&self) -> Result<Viewport, AppError> {
+        self.results
+            .first()
+            .and_then(|result| result.geometry.viewport)
+            .map(Viewport::from)
+            .ok_or(AppError::NoViewport)
+    }
+}
+
+impl From<Bounds> for Viewport {
+    fn from(bounds: Bounds) -> Self {
+        Viewport {
+            northeast: (bounds.northeast.lat, bounds.northeast.lng),
+            southwest: (bounds.southwest.lat, bounds.southwest.lng),
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use crate::error::AppError;
+use serde::Serialize;
 use serde_json::Value;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Viewport {
     pub northeast: (f64, f64),
     pub southwest: (f64, f64),
@@ -34,4 +35,29 @@ impl Viewport {
         }
         Err(AppError::NoViewport)
     }
+
+    // Reads the bounding box of the first Nominatim result, given as
+    // ["south", "north", "west", "east"] strings.
+    pub fn from_nominatim(json: &Value) -> Result<Self, AppError> {
+        let results = json.as_array().ok_or(AppError::NotAnArray)?;
+        let bounds = results
+            .first()
+            .and_then(|result| result.get("boundingbox"))
+            .and_then(Value::as_array)
+            .ok_or(AppError::NoViewport)?;
+        let edge = |i: usize| {
+            bounds
+                .get(i)
+                .and_then(|v| match v {
+                    Value::String(s) => s.parse::<f64>().ok(),
+                    other => other.as_f64(),
+                })
+                .ok_or(AppError::ValueExtractionFailed)
+        };
+
+        Ok(Viewport {
+            northeast: (edge(1)?, edge(3)?),
+            southwest: (edge(0)?, edge(2)?),
+        })
+    }
 }
@@ -0,0 +1,746 @@
+use crate::config::{
+    AdaptiveConfig, BrowserConfig, CircuitBreakerConfig, GridSpec, HttpConfig, Job, Locale,
+    RateLimit, RetryPolicy,
+};
+use crate::error::AppError;
+use crate::models::{Cell, MultiPolygon, Place, Viewport};
+use crate::services::grid_generator::{clip_cells, generate_grid_cells};
+use crate::services::quadtree::{should_split, PlannedCell};
+use crate::services::search_backend::{ScrapingBackend, SearchBackend};
+use crate::services::session::SessionPool;
+use crate::services::viewport_provider::{GoogleTextSearch, ViewportProvider};
+use crate::utils::circuit_breaker::CircuitBreaker;
+use crate::utils::http_fetcher::{HttpFetcher, ReqwestFetcher};
+use crate::utils::proxy_pool::{ProxyPool, ProxyStats};
+use crate::utils::rate_limiter::RateLimiter;
+use crate::utils::retry::with_retries;
+
+use futures::stream::FuturesUnordered;
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot};
+
+// Where a scrape searches: an area name resolved through the Places API, a
+// known viewport, or a polygon whose bounding box is searched.
+#[derive(Debug, Clone)]
+pub enum SearchArea {
+    Name(String),
+    Viewport(Viewport),
+    Polygon(Arc<MultiPolygon>),
+}
+
+// Outcome of a scrape run.
+#[derive(Debug, Serialize)]
+pub struct ScrapeReport {
+    pub places: Vec<Place>,
+    pub grid_points: usize,
+    // HTTP requests sent, retries included
+    pub requests: usize,
+    // Requests repeated after a transient failure
+    pub retries: usize,
+    // Pages given up on after their last attempt
+    pub failed_requests: usize,
+    // Pages answered with a CAPTCHA, consent or other HTML page
+    pub blocked_requests: usize,
+    // Why the circuit breaker stopped the run before every cell was searched
+    pub aborted: Option<String>,
+    // Requests served by each proxy, empty without proxies
+    pub proxies: Vec<ProxyStats>,
+    // Every searched cell, including the subdivided ones in adaptive mode
+    pub plan: Vec<PlannedCell>,
+}
+
+#[derive(Debug, Default)]
+pub struct ScraperBuilder {
+    fetcher: Option<Arc<dyn HttpFetcher>>,
+    http: HttpConfig,
+    api_key: Option<String>,
+    geocoder: Option<Arc<dyn ViewportProvider>>,
+    area: Option<SearchArea>,
+    queries: Vec<String>,
+    grid: GridSpec,
+    start: u32,
+    max_pages: Option<u32>,
+    adaptive: Option<AdaptiveConfig>,
+    rate_limit: RateLimit,
+    retry: RetryPolicy,
+    circuit_breaker: CircuitBreakerConfig,
+    clip_places: bool,
+    locale: Locale,
+    backend: Option<Arc<dyn SearchBackend>>,
+    proxies: Option<Arc<ProxyPool>>,
+    browser: BrowserConfig,
+    buffer: Option<usize>,
+}
+
+// Searches every grid point of an area for a list of queries and collects the unique places.
+#[derive(Debug, Clone)]
+pub struct Scraper {
+    backend: Arc<dyn SearchBackend>,
+    geocoder: Option<Arc<dyn ViewportProvider>>,
+    area: SearchArea,
+    queries: Vec<String>,
+    grid: GridSpec,
+    max_pages: u32,
+    adaptive: Option<AdaptiveConfig>,
+    rate_limit: RateLimit,
+    limiter: RateLimiter,
+    retry: RetryPolicy,
+    breaker: CircuitBreaker,
+    clip_places: bool,
+    buffer: usize,
+}
+
+// Places buffered between the fetching tasks and a slower consumer
+const DEFAULT_BUFFER: usize = 100;
+
+// Pages walked per grid point unless configured otherwise
+const DEFAULT_MAX_PAGES: u32 = 5;
+
+impl ScraperBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Starts a builder with the area, queries, grid and limits of a job.
+    pub fn from_job(job: &Job) -> Self {
+        Self::new()
+            .area(&job.area)
+            .queries(job.queries.iter().cloned())
+            .grid(job.grid.clone())
+            .start(job.start)
+            .max_pages(job.max_pages)
+            .adaptive(job.adaptive.clone())
+            .rate_limit(job.rate_limit.clone())
+            .retry(job.retry.clone())
+            .circuit_breaker(job.circuit_breaker.clone())
+            .browser(job.browser.clone())
+            .http(job.http.clone())
+            .clip_places(job.clip_places)
+            .locale(job.locale.clone())
+    }
+
+    // Client of the default backend and geocoder, built from `http` when missing.
+    // The session bootstrap and proxies then use clients with default settings.
+    pub fn client(self, client: reqwest::Client) -> Self {
+        self.fetcher(Arc::new(ReqwestFetcher::new(client)))
+    }
+
+    // Sends the requests of the default backend and geocoder through `fetcher`,
+    // such as a `FixtureFetcher` serving canned responses.
+    pub fn fetcher(mut self, fetcher: Arc<dyn HttpFetcher>) -> Self {
+        self.fetcher = Some(fetcher);
+        self
+    }
+
+    // Timeouts, pooling and TLS of the client built when none is given.
+    pub fn http(mut self, http: HttpConfig) -> Self {
+        self.http = http;
+        self
+    }
+
+    // Places API key used to resolve an area name to its viewport.
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    // Provider resolving an area name to its viewport.
+    pub fn geocoder(mut self, geocoder: Arc<dyn ViewportProvider>) -> Self {
+        self.geocoder = Some(geocoder);
+        self
+    }
+
+    pub fn area(mut self, area: impl Into<String>) -> Self {
+        self.area = Some(SearchArea::Name(area.into()));
+        self
+    }
+
+    pub fn viewport(mut self, viewport: Viewport) -> Self {
+        self.area = Some(SearchArea::Viewport(viewport));
+        self
+    }
+
+    // Searches only the grid cells intersecting the polygon.
+    pub fn polygon(mut self, polygon: MultiPolygon) -> Self {
+        self.area = Some(SearchArea::Polygon(Arc::new(polygon)));
+        self
+    }
+
+    // Drops places whose coordinates fall outside the search polygon.
+    pub fn clip_places(mut self, clip_places: bool) -> Self {
+        self.clip_places = clip_places;
+        self
+    }
+
+    pub fn query(mut self, query: impl Into<String>) -> Self {
+        self.queries.push(query.into());
+        self
+    }
+
+    pub fn queries<I, S>(mut self, queries: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.queries.extend(queries.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn grid(mut self, grid: GridSpec) -> Self {
+        self.grid = grid;
+        self
+    }
+
+    pub fn start(mut self, start: u32) -> Self {
+        self.start = start;
+        self
+    }
+
+    // Maximum number of pages walked per grid point, 1 disables pagination.
+    pub fn max_pages(mut self, max_pages: u32) -> Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+
+    // Splits saturated cells into quadrants within the given limits, `None` keeps the grid uniform.
+    pub fn adaptive(mut self, adaptive: Option<AdaptiveConfig>) -> Self {
+        self.adaptive = adaptive;
+        self
+    }
+
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    // Service the places are searched on, the Maps web search by default.
+    pub fn backend(mut self, backend: Arc<dyn SearchBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    // Proxies the default Maps web search backend sends its requests through.
+    pub fn proxies(mut self, proxies: Arc<ProxyPool>) -> Self {
+        self.proxies = Some(proxies);
+        self
+    }
+
+    // Browser profiles the default Maps web search backend rotates between sessions.
+    pub fn browser(mut self, browser: BrowserConfig) -> Self {
+        self.browser = browser;
+        self
+    }
+
+    // Language and region the searches are made in.
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    // How requests failing with a transient error are repeated.
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    // When blocked responses pause or abort the run.
+    pub fn circuit_breaker(mut self, circuit_breaker: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = circuit_breaker;
+        self
+    }
+
+    // Number of places buffered before fetching waits for the consumer.
+    pub fn buffer(mut self, buffer: usize) -> Self {
+        self.buffer = Some(buffer);
+        self
+    }
+
+    // Checks the settings a job shares with the builder, so `Job::validate`
+    // runs the same checks on the builder of the job.
+    pub fn validate(&self) -> Result<(), AppError> {
+        let invalid = |reason: &str| Err(AppError::InvalidScraper(reason.to_string()));
+
+        if self.queries.is_empty() {
+            return invalid("at least one query is required");
+        }
+        if self.queries.iter().any(|q| q.trim().is_empty()) {
+            return invalid("queries must not be empty");
+        }
+        self.grid.validate()?;
+        if self.max_pages == Some(0) {
+            return invalid("max_pages must be greater than zero");
+        }
+        if self.rate_limit.concurrency == 0 {
+            return invalid("rate_limit.concurrency must be greater than zero");
+        }
+        let rps = self.rate_limit.requests_per_second;
+        if rps.is_nan() || rps <= 0.0 {
+            return invalid("rate_limit.requests_per_second must be greater than zero");
+        }
+        if self.rate_limit.burst == 0 {
+            return invalid("rate_limit.burst must be greater than zero");
+        }
+        if self.retry.max_attempts == 0 {
+            return invalid("retry.max_attempts must be greater than zero");
+        }
+        if !(0.0..=1.0).contains(&self.retry.jitter) {
+            return invalid("retry.jitter must be between 0 and 1");
+        }
+        if self.retry.multiplier.is_nan() || self.retry.multiplier < 1.0 {
+            return invalid("retry.multiplier must be at least 1");
+        }
+        let breaker = &self.circuit_breaker;
+        if breaker.window == 0 {
+            return invalid("circuit_breaker.window must be greater than zero");
+        }
+        if breaker.min_requests > breaker.window {
+            return invalid("circuit_breaker.min_requests must not exceed circuit_breaker.window");
+        }
+        if !(breaker.block_threshold > 0.0 && breaker.block_threshold <= 1.0) {
+            return invalid("circuit_breaker.block_threshold must be above 0 and at most 1");
+        }
+        if self.locale.language.trim().is_empty() {
+            return invalid("locale.language must not be empty");
+        }
+        if self.buffer == Some(0) {
+            return invalid("buffer must be greater than zero");
+        }
+        Ok(())
+    }
+
+    pub fn build(self) -> Result<Scraper, AppError> {
+        let invalid = |reason: &str| AppError::InvalidScraper(reason.to_string());
+
+        self.validate()?;
+        let area = self
+            .area
+            .ok_or_else(|| invalid("an area or viewport is required"))?;
+        if matches!(area, SearchArea::Name(_)) && self.geocoder.is_none() && self.api_key.is_none()
+        {
+            return Err(invalid(
+                "an API key or geocoder is required to resolve an area name",
+            ));
+        }
+
+        let fetcher: Arc<dyn HttpFetcher> = match self.fetcher {
+            Some(fetcher) => fetcher,
+            None => Arc::new(ReqwestFetcher::from_config(&self.http)?),
+        };
+        let geocoder = match (self.geocoder, self.api_key) {
+            (Some(geocoder), _) => Some(geocoder),
+            (None, Some(api_key)) => Some(Arc::new(
+                GoogleTextSearch::new(Arc::clone(&fetcher), api_key).retry(self.retry.clone()),
+            ) as Arc<dyn ViewportProvider>),
+            (None, None) => None,
+        };
+        let backend: Arc<dyn SearchBackend> = match self.backend {
+            Some(backend) => backend,
+            None => {
+                let sessions = SessionPool::new(&self.browser, &self.locale)?;
+                Arc::new(
+                    ScrapingBackend::new(fetcher, self.locale, self.start)?
+                        .proxies(self.proxies)
+                        .sessions(Arc::new(sessions)),
+                )
+            }
+        };
+        Ok(Scraper {
+            backend,
+            geocoder,
+            area,
+            queries: self.queries,
+            grid: self.grid,
+            max_pages: self.max_pages.unwrap_or(DEFAULT_MAX_PAGES),
+            adaptive: self.adaptive,
+            limiter: RateLimiter::new(self.rate_limit.requests_per_second, self.rate_limit.burst),
+            rate_limit: self.rate_limit,
+            retry: self.retry,
+            breaker: CircuitBreaker::new(self.circuit_breaker),
+            clip_places: self.clip_places,
+            buffer: self.buffer.unwrap_or(DEFAULT_BUFFER),
+        })
+    }
+}
+
+impl Scraper {
+    pub fn builder() -> ScraperBuilder {
+        ScraperBuilder::new()
+    }
+
+    // Resolves the search area to a viewport, calling the geocoder for area names.
+    pub async fn viewport(&self) -> Result<Viewport, AppError> {
+        match &self.area {
+            SearchArea::Viewport(viewport) => Ok(viewport.clone()),
+            SearchArea::Polygon(polygon) => Ok(polygon.viewport()),
+            SearchArea::Name(name) => match &self.geocoder {
+                Some(geocoder) => geocoder.viewport(name).await,
+                None => Err(AppError::MissingApiKey),
+            },
+        }
+    }
+
+    // Generates the initial grid cells searched for each query.
+    pub async fn cells(&self) -> Result<Vec<Cell>, AppError> {
+        let viewport = self.viewport().await?;
+        let cells = generate_grid_cells(&viewport, &self.grid);
+        match &self.area {
+            SearchArea::Polygon(polygon) => {
+                let total = cells.len();
+                let cells = clip_cells(cells, polygon);
+                log::info!(
+                    "Kept {} of {} grid cells intersecting the search polygon",
+                    cells.len(),
+                    total
+                );
+                Ok(cells)
+            }
+            _ => Ok(cells),
+        }
+    }
+
+    // Polygon places must fall in to be kept, when clipping is enabled.
+    fn boundary(&self) -> Option<&MultiPolygon> {
+        match &self.area {
+            SearchArea::Polygon(polygon) if self.clip_places => Some(polygon),
+            _ => None,
+        }
+    }
+
+    // Whether a cell intersects the search polygon, always true for viewports.
+    fn reaches_area(&self, cell: &Cell) -> bool {
+        match &self.area {
+            SearchArea::Polygon(polygon) => polygon.intersects_cell(cell),
+            _ => true,
+        }
+    }
+
+    // Generates the flattened grid of (lat, long) points searched for each query.
+    pub async fn grid_points(&self) -> Result<Vec<(f64, f64)>, AppError> {
+        Ok(self.cells().await?.iter().map(Cell::center).collect())
+    }
+
+    // Collects every unique place into a report once all grid points are searched.
+    pub async fn run(&self) -> Result<ScrapeReport, AppError> {
+        let cells = self.cells().await?;
+        let (sender, receiver) = mpsc::channel(self.buffer);
+
+        let (summary, places) = tokio::join!(
+            self.fetch_grid(cells.clone(), sender),
+            receiver_stream(receiver).collect::<Vec<_>>()
+        );
+
+        Ok(ScrapeReport {
+            places,
+            grid_points: cells.len(),
+            requests: summary.requests,
+            retries: summary.retries,
+            failed_requests: summary.failed_requests,
+            blocked_requests: summary.blocked_requests,
+            aborted: self.breaker.tripped(),
+            proxies: self.backend.proxy_stats(),
+            plan: summary.plan,
+        })
+    }
+
+    // Lowest and highest number of requests needed to search `cells` cells for
+    // every query, one page per cell at least and `max_pages` at most.
+    pub fn request_estimate(&self, cells: usize) -> (usize, usize) {
+        let min_requests = cells * self.queries.len();
+        (min_requests, min_requests * self.max_pages as usize)
+    }
+
+    // Streams unique places as they are discovered. Fetching pauses while the
+    // consumer lags `buffer` places behind, and the stream ends after the last grid point.
+    // A run aborted by the circuit breaker ends with `AppError::CircuitOpen`, as `run` does.
+    pub async fn stream(&self) -> Result<impl Stream<Item = Result<Place, AppError>>, AppError> {
+        let cells = self.cells().await?;
+        let (sender, receiver) = mpsc::channel(self.buffer);
+        let (aborted_sender, aborted) = oneshot::channel();
+
+        let scraper = self.clone();
+        tokio::spawn(async move {
+            let grid_points = cells.len();
+            let summary = scraper.fetch_grid(cells, sender).await;
+            log::info!(
+                "Sent {} requests for {} grid points and {} cells, {} failed, {} blocked",
+                summary.requests,
+                grid_points,
+                summary.plan.len(),
+                summary.failed_requests,
+                summary.blocked_requests
+            );
+            let _ = aborted_sender.send(scraper.breaker.tripped());
+        });
+
+        let abort = futures::stream::once(aborted).filter_map(|tripped| async move {
+            tripped
+                .ok()
+                .flatten()
+                .map(|reason| Err(AppError::CircuitOpen(reason)))
+        });
+        Ok(receiver_stream(receiver).map(Ok).chain(abort))
+    }
+
+    // Searches every cell for every query, sending new places to `places`. In
+    // adaptive mode saturated cells are queued again as their four quadrants.
+    async fn fetch_grid(&self, cells: Vec<Cell>, places: mpsc::Sender<Place>) -> FetchSummary {
+        // Create a shared HashSet to track unique place_ids
+        let unique_places: Arc<Mutex<HashSet<String>>> = Arc::new(Mutex::new(HashSet::new()));
+
+        let mut summary = FetchSummary::default();
+
+        let (min_requests, max_requests) = self.request_estimate(cells.len());
+        log::info!(
+            "Searching {} cells for {} queries: {} to {} requests before any subdivision, \
+             {} cells at a time at {} requests per second",
+            cells.len(),
+            self.queries.len(),
+            min_requests,
+            max_requests,
+            self.rate_limit.concurrency,
+            self.rate_limit.requests_per_second
+        );
+
+        // Every (query, cell) pair is searched by its own task, at most
+        // `concurrency` at a time, while the rate limiter paces their requests
+        let mut queue: VecDeque<(Arc<str>, Cell)> = self
+            .queries
+            .iter()
+            .flat_map(|query| {
+                let query: Arc<str> = Arc::from(query.as_str());
+                cells.iter().map(move |&cell| (Arc::clone(&query), cell))
+            })
+            .collect();
+        let mut in_flight = FuturesUnordered::new();
+        loop {
+            if places.is_closed() {
+                log::info!("Place receiver dropped, stopping the scrape");
+                return summary;
+            }
+            // Cells still queued are dropped once the circuit breaker aborts the run
+            if !queue.is_empty() && self.breaker.tripped().is_some() {
+                log::warn!(
+                    "Circuit breaker open, skipping {} queued cells",
+                    queue.len()
+                );
+                queue.clear();
+            }
+            while in_flight.len() < self.rate_limit.concurrency {
+                let Some((query, cell)) = queue.pop_front() else {
+                    break;
+                };
+                let scraper = self.clone();
+                let unique_places = Arc::clone(&unique_places);
+                let places = places.clone();
+                let task_query = Arc::clone(&query);
+                let task = tokio::spawn(async move {
+                    scraper
+                        .fetch_pages(&cell, &task_query, &unique_places, &places)
+                        .await
+                });
+                in_flight.push(async move { (query, cell, task.await) });
+            }
+
+            let Some((query, cell, result)) = in_flight.next().await else {
+                break;
+            };
+            let Ok(result) = result else {
+                summary.failed_requests += 1;
+                continue;
+            };
+            summary.requests += result.requests;
+            summary.retries += result.retries;
+            summary.failed_requests += result.failed;
+            summary.blocked_requests += result.blocked;
+
+            let subdivided = self.adaptive.as_ref().is_some_and(|config| {
+                should_split(&cell, result.saturated, result.new_places, config)
+            });
+            if subdivided {
+                log::info!(
+                    "Subdividing cell at lat: {}, long: {} (depth {}, {} results, {} new)",
+                    cell.lat,
+                    cell.lng,
+                    cell.depth,
+                    result.results,
+                    result.new_places
+                );
+                queue.extend(
+                    cell.split()
+                        .into_iter()
+                        .filter(|quadrant| self.reaches_area(quadrant))
+                        .map(|quadrant| (Arc::clone(&query), quadrant)),
+                );
+            }
+            summary.plan.push(PlannedCell {
+                query: query.to_string(),
+                cell,
+                requests: result.requests,
+                retries: result.retries,
+                results: result.results,
+                new_places: result.new_places,
+                saturated: result.saturated,
+                subdivided,
+            });
+        }
+
+        summary
+    }
+
+    // Walks successive result pages of one cell until a page returns no
+    // results, adds no new place, is the last one or `max_pages` is reached.
+    async fn fetch_pages(
+        &self,
+        cell: &Cell,
+        query: &str,
+        unique_places: &Arc<Mutex<HashSet<String>>>,
+        places: &mpsc::Sender<Place>,
+    ) -> CellResult {
+        let (lat, long) = cell.center();
+        let mut result = CellResult::default();
+        let mut cursor = None;
+        for _ in 0..self.max_pages {
+            if self.breaker.wait().await.is_err() {
+                break;
+            }
+            let what = format!("Page after {:?} for lat: {}, long: {}", cursor, lat, long);
+            let (page, retries) = with_retries(&self.retry, &what, || async {
+                self.limiter.acquire().await;
+                let page = self.backend.search(cell, query, cursor.as_ref()).await;
+                self.breaker
+                    .record(page.as_ref().err().is_some_and(AppError::is_block));
+                page
+            })
+            .await;
+            result.requests += 1 + retries as usize;
+            result.retries += retries as usize;
+            let page = match page {
+                Ok(page) => page,
+                Err(e) => {
+                    crate::error_log!(
+                        "Error processing page after {:?} for lat: {}, long: {}: {}",
+                        cursor,
+                        lat,
+                        long,
+                        e
+                    );
+                    result.failed += 1;
+                    if e.is_block() {
+                        result.blocked += 1;
+                    }
+                    break;
+                }
+            };
+
+            let offset = page.offset;
+            let stats = self
+                .publish(page.places, offset, unique_places, places)
+                .await;
+            log::info!(
+                "Successfully processed page at offset {} for lat: {}, long: {} ({} results, {} new)",
+                offset,
+                lat,
+                long,
+                stats.results,
+                stats.new_places
+            );
+            result.results += stats.results;
+            result.new_places += stats.new_places;
+            result.saturated = stats.results >= self.backend.page_size();
+            match page.next {
+                Some(next) if stats.results > 0 && stats.new_places > 0 => cursor = Some(next),
+                _ => break,
+            }
+        }
+        result
+    }
+
+    // Sends the places of a page that are new and inside the boundary.
+    async fn publish(
+        &self,
+        page: Vec<Place>,
+        offset: u32,
+        unique_places: &Arc<Mutex<HashSet<String>>>,
+        places: &mpsc::Sender<Place>,
+    ) -> PageStats {
+        let mut stats = PageStats {
+            results: page.len(),
+            new_places: 0,
+        };
+
+        crate::debug_log!("Filtering out duplicate places"); // Filter out duplicate places
+        for mut place in page {
+            place.page_offset = Some(offset);
+
+            // Places outside the search polygon are dropped, places without coordinates are kept
+            if let (Some(boundary), Some(lat), Some(long)) = (
+                self.boundary(),
+                place.coordinates.lat,
+                place.coordinates.long,
+            ) {
+                if !boundary.contains(lat, long) {
+                    continue;
+                }
+            }
+
+            let is_new = if let Some(place_id) = &place.place_id {
+                // Acquire a lock on the shared HashSet and keep the place if its place_id is new
+                unique_places.lock().unwrap().insert(place_id.clone())
+            } else {
+                log::warn!("Place with no place_id found, including it in results"); // If the place has no place_id, include it (but this is unlikely)
+                true
+            };
+
+            if !is_new {
+                continue;
+            }
+            stats.new_places += 1;
+
+            // Waits while the channel is full, so a slow consumer slows down fetching
+            if places.send(place).await.is_err() {
+                crate::debug_log!("Place receiver dropped, discarding remaining places");
+                break;
+            }
+        }
+        stats
+    }
+}
+
+// What a single results page contributed to the scrape.
+#[derive(Debug, Default, Clone, Copy)]
+struct PageStats {
+    results: usize,
+    new_places: usize,
+}
+
+// Totals of a `fetch_grid` run.
+#[derive(Debug, Default)]
+struct FetchSummary {
+    requests: usize,
+    retries: usize,
+    failed_requests: usize,
+    blocked_requests: usize,
+    plan: Vec<PlannedCell>,
+}
+
+// What walking the pages of one cell produced.
+#[derive(Debug, Default)]
+struct CellResult {
+    requests: usize,
+    retries: usize,
+    failed: usize,
+    blocked: usize,
+    results: usize,
+    new_places: usize,
+    saturated: bool,
+}
+
+// Adapts a channel receiver into a `Stream` that ends once every sender is dropped.
+fn receiver_stream(receiver: mpsc::Receiver<Place>) -> impl Stream<Item = Place> {
+    futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|place| (place, receiver))
+    })
+}
@@ -0,0 +1,309 @@
+use crate::error::AppError;
+use crate::models::cell::METRES_PER_DEGREE;
+use crate::models::Viewport;
+use crate::services::viewport_provider::ViewportProvider;
+
+use async_trait::async_trait;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+// Place names and bounding boxes shipped with the crate
+const BUNDLED_CSV: &str = include_str!("../../data/gazetteer.csv");
+
+// Assumed population density, in people per km², used to size GeoNames places
+const PEOPLE_PER_KM2: f64 = 5000.0;
+
+// Bounds of the half-width given to a GeoNames place, in kilometres
+const MIN_HALF_WIDTH_KM: f64 = 1.0;
+const MAX_HALF_WIDTH_KM: f64 = 50.0;
+
+#[derive(Debug, Clone)]
+pub struct GazetteerEntry {
+    pub name: String,
+    pub alternate_names: Vec<String>,
+    // Name of the enclosing place, when the source records it
+    pub parent: Option<String>,
+    pub country_code: String,
+    pub viewport: Viewport,
+    pub population: u64,
+}
+
+// Resolves area names from a local list of places, without any network access.
+#[derive(Debug, Clone)]
+pub struct Gazetteer {
+    entries: Vec<GazetteerEntry>,
+    // Country code preferred when a name exists in several countries
+    country: Option<String>,
+}
+
+impl GazetteerEntry {
+    fn is_named(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+            || self
+                .alternate_names
+                .iter()
+                .any(|alternate| alternate.eq_ignore_ascii_case(name))
+    }
+
+    fn contains(&self, (lat, lng): (f64, f64)) -> bool {
+        let (south, west) = self.viewport.southwest;
+        let (north, east) = self.viewport.northeast;
+        (south..=north).contains(&lat) && (west..=east).contains(&lng)
+    }
+
+    fn center(&self) -> (f64, f64) {
+        let (south, west) = self.viewport.southwest;
+        let (north, east) = self.viewport.northeast;
+        ((south + north) / 2.0, (west + east) / 2.0)
+    }
+}
+
+impl Gazetteer {
+    pub fn new(entries: Vec<GazetteerEntry>) -> Self {
+        Gazetteer {
+            entries,
+            country: None,
+        }
+    }
+
+    // The CSV bundled with the crate, covering the cities we scrape most.
+    pub fn bundled() -> Self {
+        Self::new(parse_csv(BUNDLED_CSV).expect("bundled gazetteer is valid"))
+    }
+
+    // Loads a CSV file, or a GeoNames dump for any other extension.
+    pub fn load(path: &Path) -> Result<Self, AppError> {
+        let raw = fs::read_to_string(path)?;
+        let is_csv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let entries = if is_csv {
+            parse_csv(&raw)?
+        } else {
+            parse_geonames(&raw)?
+        };
+        log::info!("Loaded {} places from {}", entries.len(), path.display());
+        Ok(Self::new(entries))
+    }
+
+    pub fn country(mut self, country: Option<String>) -> Self {
+        self.country = country;
+        self
+    }
+
+    pub fn entries(&self) -> &[GazetteerEntry] {
+        &self.entries
+    }
+
+    // Resolves "Name" or "Name, Parent, ..." where each qualifier is an
+    // enclosing place or a two-letter country code.
+    pub fn resolve(&self, area: &str) -> Result<&GazetteerEntry, AppError> {
+        let not_found = || AppError::AreaNotFound(area.to_string());
+        let mut parts = area.split(',').map(str::trim).filter(|p| !p.is_empty());
+        let name = parts.next().ok_or_else(not_found)?;
+
+        let mut country = self.country.clone();
+        let mut qualifiers = Vec::new();
+        for part in parts {
+            if part.len() == 2 && self.has_country(part) {
+                country = Some(part.to_string());
+            } else {
+                qualifiers.push(part);
+            }
+        }
+
+        let mut candidates: Vec<&GazetteerEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.is_named(name))
+            .filter(|entry| {
+                country
+                    .as_deref()
+                    .is_none_or(|code| entry.country_code.eq_ignore_ascii_case(code))
+            })
+            .collect();
+        for qualifier in qualifiers {
+            candidates.retain(|entry| self.is_within(entry, qualifier));
+        }
+
+        let countries: BTreeSet<&str> = candidates
+            .iter()
+            .map(|entry| entry.country_code.as_str())
+            .collect();
+        if countries.len() > 1 {
+            return Err(AppError::AmbiguousArea {
+                area: area.to_string(),
+                countries: countries.into_iter().collect::<Vec<_>>().join(", "),
+            });
+        }
+        // Within one country, the most populous place is the one usually meant
+        candidates
+            .into_iter()
+            .max_by_key(|entry| entry.population)
+            .ok_or_else(not_found)
+    }
+
+    fn has_country(&self, code: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.country_code.eq_ignore_ascii_case(code))
+    }
+
+    // Whether the entry records the place as its parent or lies inside it.
+    fn is_within(&self, entry: &GazetteerEntry, place: &str) -> bool {
+        if entry
+            .parent
+            .as_deref()
+            .is_some_and(|parent| parent.eq_ignore_ascii_case(place))
+        {
+            return true;
+        }
+        self.entries.iter().any(|parent| {
+            parent.is_named(place)
+                && parent.country_code == entry.country_code
+                && !std::ptr::eq(parent, entry)
+                && parent.contains(entry.center())
+        })
+    }
+}
+
+#[async_trait]
+impl ViewportProvider for Gazetteer {
+    async fn viewport(&self, area: &str) -> Result<Viewport, AppError> {
+        let entry = self.resolve(area)?;
+        log::info!(
+            "Resolved {} to {} ({}) from the gazetteer",
+            area,
+            entry.name,
+            entry.country_code
+        );
+        Ok(entry.viewport.clone())
+    }
+}
+
+fn invalid(reason: String) -> AppError {
+    AppError::InvalidGazetteer(reason)
+}
+
+// CSV with a header naming at least name, country_code, south, west, north
+// and east; parent and population are optional.
+fn parse_csv(raw: &str) -> Result<Vec<GazetteerEntry>, AppError> {
+    let mut lines = raw.lines().filter(|line| !line.trim().is_empty());
+    let header = split_csv_line(lines.next().unwrap_or_default());
+    let column = |name: &str| header.iter().position(|h| h.trim() == name);
+    let required =
+        |name: &str| column(name).ok_or_else(|| invalid(format!("missing {} column", name)));
+    let (name, country_code) = (required("name")?, required("country_code")?);
+    let edges = [
+        required("south")?,
+        required("west")?,
+        required("north")?,
+        required("east")?,
+    ];
+    let (parent, population) = (column("parent"), column("population"));
+
+    lines
+        .enumerate()
+        .map(|(i, line)| {
+            let fields = split_csv_line(line);
+            let field = |index: usize| fields.get(index).map(|f| f.trim()).unwrap_or_default();
+            let number = |index: usize| {
+                field(index)
+                    .parse::<f64>()
+                    .map_err(|_| invalid(format!("line {}: invalid coordinate", i + 2)))
+            };
+            let [south, west, north, east] = [
+                number(edges[0])?,
+                number(edges[1])?,
+                number(edges[2])?,
+                number(edges[3])?,
+            ];
+            Ok(GazetteerEntry {
+                name: field(name).to_string(),
+                alternate_names: Vec::new(),
+                parent: parent
+                    .map(field)
+                    .filter(|p| !p.is_empty())
+                    .map(str::to_string),
+                country_code: field(country_code).to_string(),
+                viewport: Viewport {
+                    northeast: (north, east),
+                    southwest: (south, west),
+                },
+                population: population
+                    .and_then(|index| field(index).parse().ok())
+                    .unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+// Splits one CSV line, honouring double-quoted fields.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+// Tab-separated GeoNames dump (allCountries.txt, cities500.txt, ...). GeoNames
+// only records a point, so the box is sized from the population.
+fn parse_geonames(raw: &str) -> Result<Vec<GazetteerEntry>, AppError> {
+    raw.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 15 {
+                return Err(invalid(format!(
+                    "line {}: expected at least 15 columns, found {}",
+                    i + 1,
+                    fields.len()
+                )));
+            }
+            let coordinate = |index: usize| {
+                fields[index]
+                    .parse::<f64>()
+                    .map_err(|_| invalid(format!("line {}: invalid coordinate", i + 1)))
+            };
+            let (lat, lng) = (coordinate(4)?, coordinate(5)?);
+            let population: u64 = fields[14].parse().unwrap_or_default();
+
+            let half_width_km = ((population as f64 / PEOPLE_PER_KM2).sqrt() / 2.0)
+                .clamp(MIN_HALF_WIDTH_KM, MAX_HALF_WIDTH_KM);
+            let lat_offset = half_width_km * 1000.0 / METRES_PER_DEGREE;
+            let lng_offset = lat_offset / lat.to_radians().cos().max(0.01);
+
+            let mut alternate_names = vec![fields[2].to_string()];
+            alternate_names.extend(
+                fields[3]
+                    .split(',')
+                    .filter(|n| !n.is_empty())
+                    .map(str::to_string),
+            );
+            Ok(GazetteerEntry {
+                name: fields[1].to_string(),
+                alternate_names,
+                parent: None,
+                country_code: fields[8].to_string(),
+                viewport: Viewport {
+                    northeast: (lat + lat_offset, lng + lng_offset),
+                    southwest: (lat - lat_offset, lng - lng_offset),
+                },
+                population,
+            })
+        })
+        .collect()
+}
@@ -1,86 +1,196 @@
-use crate::{debug_log, error_log};
+use crate::debug_log;
 use crate::{
+    config::{Locale, RetryPolicy},
     error::AppError,
-    models::{Coordinates, Place},
-    utils::http_client::create_headers,
+    models::{Cell, Coordinates, Place, TextSearchResponse},
+    services::pb::{SearchPb, SessionTokens},
+    services::response_classifier::{classify, ResponseKind},
+    utils::http_fetcher::HttpFetcher,
     utils::json_utils::{build_results, prepare},
+    utils::retry::with_retries,
 };
 
-use serde_json::Value;
-use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
+use reqwest::header::HeaderMap;
+use url::form_urlencoded::Serializer;
+use url::Url;
 
-pub async fn search_location(api_key: &str, query: &str) -> Result<Value, AppError> {
-    let url = format!(
-        "https://maps.googleapis.com/maps/api/place/textsearch/json?query={}&key={}",
-        query, api_key
-    );
+const TEXT_SEARCH_URL: &str = "https://maps.googleapis.com/maps/api/place/textsearch/json";
+pub const SEARCH_URL: &str = "https://www.google.com/search";
 
-    log::info!("Fetching search location");
-    let response = reqwest::get(&url).await?;
+// Places Text Search request for a query, with every parameter percent-encoded.
+pub fn text_search_url(api_key: &str, query: &str) -> Url {
+    Url::parse_with_params(TEXT_SEARCH_URL, &[("query", query), ("key", api_key)])
+        .expect("TEXT_SEARCH_URL is a valid URL")
+}
+
+// Runs a Text Search, retrying quota and transient server errors as `retry`
+// allows, and fails with the error matching any status other than OK.
+pub async fn search_location(
+    fetcher: &dyn HttpFetcher,
+    api_key: &str,
+    query: &str,
+    retry: &RetryPolicy,
+) -> Result<TextSearchResponse, AppError> {
+    let url = &text_search_url(api_key, query);
 
-    log::info!("Search API Response status: {}", response.status());
-    let json: Value = response.json().await?;
+    let (result, _) = with_retries(retry, "Text Search", move || async move {
+        log::info!("Fetching search location");
+        let response = fetcher.get(url, &HeaderMap::new()).await?;
 
-    Ok(json)
+        log::info!("Search API Response status: {}", response.status);
+        response.json::<TextSearchResponse>()?.check_status(query)
+    })
+    .await;
+    result
 }
 
-pub fn build_url(lat: f64, long: f64, start: u32, query: &str) -> String {
-    let base_url = "https://www.google.com/search";
-    let params = format!(
-        "tbm=map&authuser=0&hl=en&pb=!4m12!1m3!1d13499.795714815926!2d{}!3d{}!2m3!1f0!2f0!3f0!3m2!1i445!2i621!4f13.1!7i{}!10b1!12m21!1m2!18b1!30b1!2m3!5m1!6e2!20e3!10b1!12b1!13b1!16b1!17m1!3e1!20m3!5e2!6b1!14b1!46m1!1b0!94b1!96b1!19m4!2m3!1i360!2i120!4i8!20m57!2m2!1i203!2i100!3m2!2i4!5b1!6m6!1m2!1i86!2i86!1m2!1i408!2i240!7m42!1m3!1e1!2b0!3e3!1m3!1e2!2b1!3e2!1m3!1e2!2b0!3e3!1m3!1e8!2b0!3e3!1m3!1e10!2b0!3e3!1m3!1e10!2b1!3e2!1m3!1e9!2b1!3e2!1m3!1e10!2b0!3e3!1m3!1e10!2b1!3e2!1m3!1e10!2b0!3e4!2b1!4b1!9b0!22m5!1s1pk8Z7ObMvuRkdUPhKD9iAY%3A60!2s1i%3A0%2Ct%3A150715%2Cp%3A1pk8Z7ObMvuRkdUPhKD9iAY%3A60!7e81!12e3!17s1pk8Z7ObMvuRkdUPhKD9iAY%3A66!24m105!1m32!13m9!2b1!3b1!4b1!6i1!8b1!9b1!14b1!20b1!25b1!18m21!3b1!4b1!5b1!6b1!9b1!12b1!13b1!14b1!17b1!20b1!21b1!22b1!25b1!27m1!1b0!28b0!32b0!33m1!1b1!34b0!36e1!10m1!8e3!11m1!3e1!14m1!3b1!17b1!20m2!1e3!1e6!24b1!25b1!26b1!29b1!30m1!2b1!36b1!39m3!2m2!2i1!3i1!43b1!52b1!54m1!1b1!55b1!56m1!1b1!65m5!3m4!1m3!1m2!1i224!2i298!71b1!72m19!1m5!1b1!2b1!3b1!5b1!7b1!4b1!8m10!1m6!4m1!1e1!4m1!1e3!4m1!1e4!3sother_user_reviews!6m1!1e1!9b1!89b1!98m3!1b1!2b1!3b1!103b1!113b1!114m3!1b1!2m1!1b1!117b1!122m1!1b1!125b0!126b1!127b1!26m4!2m3!1i80!2i92!4i8!30m0!34m18!2b1!3b1!4b1!6b1!8m6!1b1!3b1!4b1!5b1!6b1!7b1!9b1!12b1!14b1!20b1!23b1!25b1!26b1!37m1!1e81!42b1!47m0!49m9!3b1!6m2!1b1!2b1!7m2!1e3!2b1!8b1!9b1!50m4!2e2!3m2!1b1!3b1!67m2!7b1!10b1!69i713&q={}&nfpr=1&tch=1&ech=1&psi=1pk8Z7ObMvuRkdUPhKD9iAY.1732024792533.1",
+// Metres per pixel at zoom level 0 on the equator
+const METRES_PER_PIXEL_Z0: f64 = 156_543.033_92;
+
+// Height in pixels of the map a search is made from
+const SCREEN_HEIGHT_PX: u32 = 621;
 
-        lat, long, start, query
-    );
-    format!("{}?{}", base_url, params)
+// Narrowest and widest screens a search is made from
+const SCREEN_WIDTH_PX: (u32, u32) = (100, 4096);
+
+// The map view a search request is made from: `!1d` span, `!3m2` screen size and `!4f` zoom.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MapView {
+    // Height of the visible map in metres
+    pub span_m: f64,
+    pub width_px: u32,
+    pub height_px: u32,
+    pub zoom: f64,
 }
 
-pub async fn fetch_and_process_data(
-    client: &reqwest::Client,
-    url: &str,
-    unique_places: &Arc<Mutex<HashSet<String>>>,
-    all_places: &Arc<Mutex<Vec<Place>>>,
-    searched_coords: Coordinates,
-) -> Result<(), AppError> {
-    debug_log!("Fetching data from URL: {}", url); // Make the request and process the response
+impl MapView {
+    // View of the browser session the request template was captured from.
+    pub const DEFAULT: MapView = MapView {
+        span_m: 13499.795714815926,
+        width_px: 445,
+        height_px: 621,
+        zoom: 13.1,
+    };
+
+    // View showing the whole cell: the screen has the aspect ratio of the cell
+    // and the zoom fits the cell to the screen. Cells too thin for the widest
+    // or narrowest screen are fitted along their longer side, so the view
+    // reaches past the cell the other way instead of leaving part of it out.
+    pub fn for_cell(cell: &Cell) -> Result<Self, AppError> {
+        let (height_m, width_m) = cell.size_m();
+        if !(height_m > 0.0 && width_m > 0.0 && height_m.is_finite() && width_m.is_finite()) {
+            return Err(AppError::InvalidGrid(format!(
+                "the cell at {}, {} has no area to search",
+                cell.lat, cell.lng
+            )));
+        }
 
-    let response = client.get(url).headers(create_headers()).send().await?;
+        let height_px = SCREEN_HEIGHT_PX;
+        let (min_width, max_width) = SCREEN_WIDTH_PX;
+        // Rounded up, so the screen never falls short of the cell width
+        let width_px = ((height_px as f64) * width_m / height_m)
+            .ceil()
+            .clamp(min_width as f64, max_width as f64) as u32;
 
-    debug_log!("Response status: {}, URL: {}", response.status(), url);
+        let metres_per_pixel = (height_m / height_px as f64).max(width_m / width_px as f64);
+        let zoom = (METRES_PER_PIXEL_Z0 * cell.lat.to_radians().cos() / metres_per_pixel).log2();
 
-    if !response.status().is_success() {
-        error_log!(
-            "Request returned non-200 status code: {} for URL: {}",
-            response.status(),
-            url
-        );
-        return Ok(()); // Return early but successfully to continue with other requests
+        Ok(MapView {
+            span_m: metres_per_pixel * height_px as f64,
+            width_px,
+            height_px,
+            zoom: (zoom * 100.0).round() / 100.0,
+        })
     }
+}
 
-    let raw_input = response.text().await?;
+// Maps search request for a query around a point, made with the tokens of the
+// captured browser session.
+pub fn build_url(
+    lat: f64,
+    long: f64,
+    view: &MapView,
+    start: u32,
+    query: &str,
+    locale: &Locale,
+) -> String {
+    let pb = SearchPb {
+        lat,
+        lng: long,
+        view: *view,
+        start,
+        offset: None,
+        session: SessionTokens::captured(),
+    };
+    build_search_url(SEARCH_URL, &pb, query, locale).expect("SEARCH_URL is a valid URL")
+}
 
-    debug_log!("Preparing and processing JSON data");
-    let prepared_data = prepare(&raw_input)?;
-    let list_results = build_results(&prepared_data, searched_coords.clone())?;
-
-    debug_log!("Filtering out duplicate places"); // Filter out duplicate places
-    for place in list_results {
-        let place_id = place.place_id.clone();
-        // Acquire a lock on the shared HashSet
-        let mut unique_places = unique_places.lock().unwrap();
-        if let Some(place_id) = place_id {
-            // If the place_id is not in the HashSet, add it and keep the place
-            if !unique_places.contains(&place_id) {
-                unique_places.insert(place_id);
-                let mut all_places = all_places.lock().unwrap();
-                all_places.push(place);
-            }
-        } else {
-            log::warn!("Place with no place_id found, including it in results"); // If the place has no place_id, include it (but this is unlikely)
-            let mut all_places = all_places.lock().unwrap();
-            all_places.push(place);
+// Maps search request for a `pb` against any endpoint, such as a local mock
+// server. The `pb` keeps its `!` and `*` separators literal as the browser
+// sends them; every other parameter is form-encoded.
+pub fn build_search_url(
+    base_url: &str,
+    pb: &SearchPb,
+    query: &str,
+    locale: &Locale,
+) -> Result<String, AppError> {
+    let mut head = Serializer::new(String::new());
+    head.append_pair("tbm", "map")
+        .append_pair("authuser", "0")
+        .append_pair("hl", &locale.language);
+    if let Some(region) = &locale.region {
+        head.append_pair("gl", region);
+    }
+    let tail = Serializer::new(String::new())
+        .append_pair("q", query)
+        .append_pair("nfpr", "1")
+        .append_pair("tch", "1")
+        .append_pair("ech", "1")
+        .append_pair("psi", &pb.session.psi())
+        .finish();
+
+    let mut url = Url::parse(base_url)?;
+    url.set_query(Some(&format!(
+        "{}&pb={}&{}",
+        head.finish(),
+        pb.render_encoded(),
+        tail
+    )));
+    Ok(url.into())
+}
+
+// Number of results Google Maps returns per page
+pub const PAGE_SIZE: u32 = 20;
+
+// Fetches one results page with the headers of a browser profile and parses its
+// places. CAPTCHA, consent and other HTML pages fail with their own error
+// instead of reaching the JSON parser.
+pub async fn fetch_places(
+    fetcher: &dyn HttpFetcher,
+    url: &str,
+    headers: &HeaderMap,
+    searched_coords: Coordinates,
+) -> Result<Vec<Place>, AppError> {
+    debug_log!("Fetching data from URL: {}", url); // Make the request and process the response
+
+    let response = fetcher.get(&Url::parse(url)?, headers).await?;
+
+    debug_log!("Response status: {}, URL: {}", response.status, url);
+
+    match classify(&response.url, &response.body) {
+        ResponseKind::Captcha => return Err(AppError::CaptchaPage(response.url.into())),
+        ResponseKind::ConsentPage => return Err(AppError::ConsentPage(response.url.into())),
+        _ if !response.status.is_success() => {
+            return Err(AppError::HttpStatus {
+                status: response.status.as_u16(),
+                retry_after: response.retry_after(),
+            })
         }
+        ResponseKind::UnknownHtml => return Err(AppError::UnexpectedHtml(response.url.into())),
+        ResponseKind::Data => {}
     }
 
-    Ok(())
+    debug_log!("Preparing and processing JSON data");
+    let prepared_data = prepare(&response.body)?;
+    build_results(&prepared_data, searched_coords)
 }
@@ -1,23 +1,251 @@
+use crate::config::GridSpec;
+use crate::error::AppError;
+use crate::models::cell::{Cell, METRES_PER_DEGREE};
+use crate::models::polygon::MultiPolygon;
 use crate::models::viewport::Viewport;
 
-pub fn generate_grid(viewport: &Viewport, rows: usize, cols: usize) -> Vec<Vec<(f64, f64)>> {
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+// Layout of the search points over an area.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tiling {
+    // Rows x cols or metric cells, searched at their centres
+    #[default]
+    Grid,
+    // Triangular lattice of search circles, the cheapest layout without gaps
+    Hex,
+    // Square lattice of search circles circumscribing each square
+    Circle,
+}
+
+// Share of an area reached by a tiling, and how much its search circles overlap.
+#[derive(Debug, Clone, Serialize)]
+pub struct TilingStats {
+    pub tiling: Tiling,
+    pub points: usize,
+    // Fraction of the area within the search radius of a point
+    pub coverage: f64,
+    // Average number of extra circles covering a covered location
+    pub overlap: f64,
+}
+
+// Samples per side used to estimate coverage and overlap
+const STATS_SAMPLES: usize = 200;
+
+// Splits the viewport into rows x cols cells of one grid step, which tile it
+// exactly from the south-west corner to the north-east one.
+pub fn generate_cells(viewport: &Viewport, rows: usize, cols: usize) -> Vec<Cell> {
     let (ne_lat, ne_lng) = viewport.northeast;
     let (sw_lat, sw_lng) = viewport.southwest;
 
     let lat_step = (ne_lat - sw_lat) / (rows as f64);
     let lng_step = (ne_lng - sw_lng) / (cols as f64);
 
-    let mut grid = Vec::with_capacity(rows);
+    let mut cells = Vec::with_capacity(rows * cols);
+    for i in 0..rows {
+        let lat = sw_lat + (i as f64 + 0.5) * lat_step;
+        for j in 0..cols {
+            let lng = sw_lng + (j as f64 + 0.5) * lng_step;
+            cells.push(Cell::new(lat, lng, lat_step, lng_step));
+        }
+    }
+
+    cells
+}
+
+// Covers the whole viewport, edges included, with cells of about `cell_size_m` metres.
+// Longitude spacing is widened by latitude so that cells stay square on the ground.
+pub fn generate_metric_grid(viewport: &Viewport, cell_size_m: f64) -> Vec<Cell> {
+    let (ne_lat, ne_lng) = viewport.northeast;
+    let (sw_lat, sw_lng) = viewport.southwest;
 
+    let height_m = (ne_lat - sw_lat) * METRES_PER_DEGREE;
+    let rows = ((height_m / cell_size_m).ceil() as usize).max(1);
+    let lat_step = (ne_lat - sw_lat) / (rows as f64);
+
+    let mut cells = Vec::new();
     for i in 0..rows {
-        let mut row = Vec::with_capacity(cols);
+        let lat = sw_lat + (i as f64 + 0.5) * lat_step;
+        let width_m = (ne_lng - sw_lng) * METRES_PER_DEGREE * lat.to_radians().cos();
+        let cols = ((width_m / cell_size_m).ceil() as usize).max(1);
+        let lng_step = (ne_lng - sw_lng) / (cols as f64);
+
         for j in 0..cols {
-            let lat = sw_lat + (i as f64) * lat_step;
-            let lng = sw_lng + (j as f64) * lng_step;
-            row.push((lat, lng));
+            let lng = sw_lng + (j as f64 + 0.5) * lng_step;
+            cells.push(Cell::new(lat, lng, lat_step, lng_step));
         }
-        grid.push(row);
     }
 
-    grid
+    cells
+}
+
+// Places search points of radius `radius_m` on a triangular lattice, every
+// location of the viewport lying within the radius of at least one point.
+pub fn generate_hex_grid(viewport: &Viewport, radius_m: f64) -> Vec<(f64, f64)> {
+    let spacing = radius_m * 3f64.sqrt();
+    generate_lattice(viewport, spacing, spacing * 3f64.sqrt() / 2.0, true)
+}
+
+// Places search points of radius `radius_m` on a square lattice whose squares
+// are inscribed in the search circles.
+pub fn generate_circle_grid(viewport: &Viewport, radius_m: f64) -> Vec<(f64, f64)> {
+    let spacing = radius_m * 2f64.sqrt();
+    generate_lattice(viewport, spacing, spacing, false)
+}
+
+// Lays rows `row_spacing_m` apart from the south-west corner past the
+// north-east edge, shifting every other row by half a step when `staggered`.
+fn generate_lattice(
+    viewport: &Viewport,
+    spacing_m: f64,
+    row_spacing_m: f64,
+    staggered: bool,
+) -> Vec<(f64, f64)> {
+    let (ne_lat, ne_lng) = viewport.northeast;
+    let (sw_lat, sw_lng) = viewport.southwest;
+
+    let lat_step = row_spacing_m / METRES_PER_DEGREE;
+    let rows = ((ne_lat - sw_lat) / lat_step).ceil() as usize + 1;
+
+    let mut points = Vec::new();
+    for i in 0..rows {
+        let lat = sw_lat + (i as f64) * lat_step;
+        let lng_step = spacing_m / (METRES_PER_DEGREE * lat.to_radians().cos());
+        let shift = if staggered && i % 2 == 1 {
+            -lng_step / 2.0
+        } else {
+            0.0
+        };
+        let cols = ((ne_lng - sw_lng - shift) / lng_step).ceil() as usize + 1;
+
+        for j in 0..cols {
+            points.push((lat, sw_lng + shift + (j as f64) * lng_step));
+        }
+    }
+
+    points
+}
+
+// Turns search points into cells bounding their search circle.
+fn circle_cells(points: Vec<(f64, f64)>, radius_m: f64) -> Vec<Cell> {
+    points
+        .into_iter()
+        .map(|(lat, lng)| {
+            let lat_span = 2.0 * radius_m / METRES_PER_DEGREE;
+            let lng_span = lat_span / lat.to_radians().cos();
+            Cell::new(lat, lng, lat_span, lng_span)
+        })
+        .collect()
+}
+
+// Generates the initial cells described by a grid spec.
+pub fn generate_grid_cells(viewport: &Viewport, spec: &GridSpec) -> Vec<Cell> {
+    match (spec.tiling, spec.search_radius_m) {
+        (Tiling::Hex, Some(radius_m)) => {
+            circle_cells(generate_hex_grid(viewport, radius_m), radius_m)
+        }
+        (Tiling::Circle, Some(radius_m)) => {
+            circle_cells(generate_circle_grid(viewport, radius_m), radius_m)
+        }
+        _ => match spec.cell_size_m {
+            Some(cell_size_m) => generate_metric_grid(viewport, cell_size_m),
+            None => generate_cells(viewport, spec.rows, spec.cols),
+        },
+    }
+}
+
+// Drops the cells that do not intersect the boundary of a search area.
+pub fn clip_cells(cells: Vec<Cell>, boundary: &MultiPolygon) -> Vec<Cell> {
+    cells
+        .into_iter()
+        .filter(|cell| boundary.intersects_cell(cell))
+        .collect()
+}
+
+// Estimates which share of the viewport lies within `radius_m` of a point and
+// how many points reach each covered location, on a fixed grid of samples.
+pub fn tiling_stats(
+    tiling: Tiling,
+    viewport: &Viewport,
+    points: &[(f64, f64)],
+    radius_m: f64,
+) -> TilingStats {
+    let (ne_lat, ne_lng) = viewport.northeast;
+    let (sw_lat, sw_lng) = viewport.southwest;
+    let lat_step = (ne_lat - sw_lat) / STATS_SAMPLES as f64;
+    let lng_step = (ne_lng - sw_lng) / STATS_SAMPLES as f64;
+
+    let sample = |index: usize, origin: f64, step: f64| origin + (index as f64 + 0.5) * step;
+    // Range of sample indexes between two coordinates, clamped to the viewport
+    let range = |from: f64, to: f64, origin: f64, step: f64| {
+        let first = ((from - origin) / step - 0.5).ceil().max(0.0) as usize;
+        let last = (((to - origin) / step - 0.5).floor() + 1.0).clamp(0.0, STATS_SAMPLES as f64);
+        first..last as usize
+    };
+
+    let mut hits = vec![0u32; STATS_SAMPLES * STATS_SAMPLES];
+    for &(lat, lng) in points {
+        let metres_per_lng = METRES_PER_DEGREE * lat.to_radians().cos();
+        let lat_radius = radius_m / METRES_PER_DEGREE;
+        let lng_radius = radius_m / metres_per_lng;
+
+        for i in range(lat - lat_radius, lat + lat_radius, sw_lat, lat_step) {
+            let dy = (sample(i, sw_lat, lat_step) - lat) * METRES_PER_DEGREE;
+            for j in range(lng - lng_radius, lng + lng_radius, sw_lng, lng_step) {
+                let dx = (sample(j, sw_lng, lng_step) - lng) * metres_per_lng;
+                if dx * dx + dy * dy <= radius_m * radius_m {
+                    hits[i * STATS_SAMPLES + j] += 1;
+                }
+            }
+        }
+    }
+
+    let covered = hits.iter().filter(|&&count| count > 0).count();
+    let total_hits: u32 = hits.iter().sum();
+    TilingStats {
+        tiling,
+        points: points.len(),
+        coverage: covered as f64 / hits.len() as f64,
+        overlap: if covered > 0 {
+            total_hits as f64 / covered as f64 - 1.0
+        } else {
+            0.0
+        },
+    }
+}
+
+// Compares every tiling of the viewport for a given search radius.
+pub fn compare_tilings(
+    viewport: &Viewport,
+    spec: &GridSpec,
+    radius_m: f64,
+) -> Result<Vec<TilingStats>, AppError> {
+    let grid = GridSpec {
+        tiling: Tiling::Grid,
+        search_radius_m: Some(radius_m),
+        ..spec.clone()
+    };
+    grid.validate()?;
+    let grid_points: Vec<(f64, f64)> = generate_grid_cells(viewport, &grid)
+        .iter()
+        .map(Cell::center)
+        .collect();
+
+    Ok(vec![
+        tiling_stats(Tiling::Grid, viewport, &grid_points, radius_m),
+        tiling_stats(
+            Tiling::Hex,
+            viewport,
+            &generate_hex_grid(viewport, radius_m),
+            radius_m,
+        ),
+        tiling_stats(
+            Tiling::Circle,
+            viewport,
+            &generate_circle_grid(viewport, radius_m),
+            radius_m,
+        ),
+    ])
 }
@@ -1,2 +1,11 @@
+pub mod gazetteer;
 pub mod google_maps;
 pub mod grid_generator;
+pub mod pb;
+pub mod pb_decoder;
+pub mod places_api;
+pub mod quadtree;
+pub mod response_classifier;
+pub mod search_backend;
+pub mod session;
+pub mod viewport_provider;
@@ -0,0 +1,436 @@
+use crate::services::google_maps::MapView;
+
+use rand::Rng;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// One `!<number><type><value>` field of a Google Maps `pb` parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PbField {
+    pub number: u32,
+    pub value: PbValue,
+}
+
+// Values of the protobuf-over-URL encoding, keyed by their type letter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PbValue {
+    // `m`, rendered with the number of fields nested below it
+    Message(Vec<PbField>),
+    // `d`
+    Double(f64),
+    // `f`
+    Float(f64),
+    // `i`
+    Int(i64),
+    // `b`
+    Bool(bool),
+    // `e`
+    Enum(i64),
+    // `s`
+    String(String),
+    // Any other type letter, such as `z` or `v`, kept verbatim
+    Other(char, String),
+}
+
+// Builds a `pb` parameter field by field.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PbBuilder {
+    fields: Vec<PbField>,
+}
+
+// Session identifiers Google ties a search to, normally issued with the Maps page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionTokens {
+    pub ei: String,
+    // Page load time in milliseconds, sent in `psi`
+    pub timestamp_ms: u64,
+}
+
+// Parameters of a Maps search `pb`.
+#[derive(Debug, Clone)]
+pub struct SearchPb {
+    pub lat: f64,
+    pub lng: f64,
+    pub view: MapView,
+    // Field 7, the number of results per page
+    pub start: u32,
+    // Field 8, the result offset, which the web client leaves out of the first page
+    pub offset: Option<u32>,
+    pub session: SessionTokens,
+}
+
+impl PbValue {
+    pub fn type_letter(&self) -> char {
+        match self {
+            PbValue::Message(_) => 'm',
+            PbValue::Double(_) => 'd',
+            PbValue::Float(_) => 'f',
+            PbValue::Int(_) => 'i',
+            PbValue::Bool(_) => 'b',
+            PbValue::Enum(_) => 'e',
+            PbValue::String(_) => 's',
+            PbValue::Other(letter, _) => *letter,
+        }
+    }
+}
+
+impl PbField {
+    // Number of fields nested below this one, the count rendered after `m`.
+    pub fn descendants(&self) -> usize {
+        match &self.value {
+            PbValue::Message(fields) => fields.iter().map(|f| 1 + f.descendants()).sum(),
+            _ => 0,
+        }
+    }
+
+    fn render(&self, output: &mut String) {
+        output.push('!');
+        output.push_str(&self.number.to_string());
+        output.push(self.value.type_letter());
+        match &self.value {
+            PbValue::Message(fields) => {
+                output.push_str(&self.descendants().to_string());
+                for field in fields {
+                    field.render(output);
+                }
+            }
+            PbValue::Double(value) | PbValue::Float(value) => output.push_str(&value.to_string()),
+            PbValue::Int(value) | PbValue::Enum(value) => output.push_str(&value.to_string()),
+            PbValue::Bool(value) => output.push(if *value { '1' } else { '0' }),
+            PbValue::String(value) => output.push_str(&escape_string(value)),
+            PbValue::Other(_, value) => output.push_str(value),
+        }
+    }
+}
+
+impl PbBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field(mut self, number: u32, value: PbValue) -> Self {
+        self.fields.push(PbField { number, value });
+        self
+    }
+
+    // Adds a nested message whose fields are added by `build`.
+    pub fn message(self, number: u32, build: impl FnOnce(PbBuilder) -> PbBuilder) -> Self {
+        let fields = build(PbBuilder::new()).fields;
+        self.field(number, PbValue::Message(fields))
+    }
+
+    pub fn double(self, number: u32, value: f64) -> Self {
+        self.field(number, PbValue::Double(value))
+    }
+
+    pub fn float(self, number: u32, value: f64) -> Self {
+        self.field(number, PbValue::Float(value))
+    }
+
+    pub fn int(self, number: u32, value: i64) -> Self {
+        self.field(number, PbValue::Int(value))
+    }
+
+    pub fn bool(self, number: u32, value: bool) -> Self {
+        self.field(number, PbValue::Bool(value))
+    }
+
+    pub fn enum_value(self, number: u32, value: i64) -> Self {
+        self.field(number, PbValue::Enum(value))
+    }
+
+    pub fn string(self, number: u32, value: impl Into<String>) -> Self {
+        self.field(number, PbValue::String(value.into()))
+    }
+
+    pub fn fields(&self) -> &[PbField] {
+        &self.fields
+    }
+
+    pub fn into_fields(self) -> Vec<PbField> {
+        self.fields
+    }
+
+    // Renders the fields as a `pb` value, before URL encoding.
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        for field in &self.fields {
+            field.render(&mut output);
+        }
+        output
+    }
+
+    // Renders the fields percent-encoded for a query string, keeping `!` and `*` as browsers do.
+    pub fn render_encoded(&self) -> String {
+        encode_pb(&self.render())
+    }
+}
+
+impl SessionTokens {
+    // Tokens of the browser session the search template was captured from.
+    pub fn captured() -> Self {
+        SessionTokens {
+            ei: "1pk8Z7ObMvuRkdUPhKD9iAY".to_string(),
+            timestamp_ms: 1732024792533,
+        }
+    }
+
+    // Tokens for a new session: a random `ei` in the URL-safe base64 alphabet
+    // Google uses, and the current time as page load time.
+    pub fn generate() -> Self {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+        let mut rng = rand::thread_rng();
+        let ei = (0..23)
+            .map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())] as char)
+            .collect();
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        SessionTokens { ei, timestamp_ms }
+    }
+
+    // Value of the `psi` query parameter.
+    pub fn psi(&self) -> String {
+        format!("{}.{}.1", self.ei, self.timestamp_ms)
+    }
+}
+
+impl SearchPb {
+    // Builds the `pb` the way the Maps web client sends a search.
+    pub fn builder(&self) -> PbBuilder {
+        let pb = PbBuilder::new()
+            .message(4, |m| {
+                m.message(1, |m| {
+                    m.double(1, self.view.span_m)
+                        .double(2, self.lng)
+                        .double(3, self.lat)
+                })
+                .message(2, |m| m.float(1, 0.0).float(2, 0.0).float(3, 0.0))
+                .message(3, |m| {
+                    m.int(1, self.view.width_px as i64)
+                        .int(2, self.view.height_px as i64)
+                })
+                .float(4, self.view.zoom)
+            })
+            .int(7, self.start as i64);
+        let pb = match self.offset {
+            Some(offset) => pb.int(8, offset as i64),
+            None => pb,
+        };
+        let pb = photo_options(result_options(pb.bool(10, true)));
+        place_fields(session_fields(pb, &self.session))
+    }
+
+    pub fn render_encoded(&self) -> String {
+        self.builder().render_encoded()
+    }
+}
+
+// `*` and `!` inside strings would be read as field separators.
+fn escape_string(value: &str) -> String {
+    value.replace('*', "*2A").replace('!', "*21")
+}
+
+// Percent-encodes everything but unreserved characters and the `pb` separators.
+pub fn encode_pb(pb: &str) -> String {
+    let mut encoded = String::with_capacity(pb.len());
+    for byte in pb.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'!' | b'*' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn session_fields(pb: PbBuilder, session: &SessionTokens) -> PbBuilder {
+    pb.message(22, |m| {
+        m.string(1, format!("{}:60", session.ei))
+            .string(2, format!("1i:0,t:150715,p:{}:60", session.ei))
+            .enum_value(7, 81)
+            .enum_value(12, 3)
+            .string(17, format!("{}:66", session.ei))
+    })
+}
+
+// The remaining fields are copied from a browser capture and select which
+// details, photos and reviews come back with each result.
+fn result_options(pb: PbBuilder) -> PbBuilder {
+    pb.message(12, |m| {
+        m.message(1, |m| m.bool(18, true).bool(30, true))
+            .message(2, |m| {
+                m.message(5, |m| m.enum_value(6, 2)).enum_value(20, 3)
+            })
+            .bool(10, true)
+            .bool(12, true)
+            .bool(13, true)
+            .bool(16, true)
+            .message(17, |m| m.enum_value(3, 1))
+            .message(20, |m| m.enum_value(5, 2).bool(6, true).bool(14, true))
+            .message(46, |m| m.bool(1, false))
+            .bool(94, true)
+            .bool(96, true)
+    })
+}
+
+fn photo_options(pb: PbBuilder) -> PbBuilder {
+    pb.message(19, |m| {
+        m.message(2, |m| m.int(1, 360).int(2, 120).int(4, 8))
+    })
+    .message(20, |m| {
+        m.message(2, |m| m.int(1, 203).int(2, 100))
+            .message(3, |m| m.int(2, 4).bool(5, true))
+            .message(6, |m| {
+                m.message(1, |m| m.int(1, 86).int(2, 86))
+                    .message(1, |m| m.int(1, 408).int(2, 240))
+            })
+            .message(7, |m| {
+                m.message(1, |m| m.enum_value(1, 1).bool(2, false).enum_value(3, 3))
+                    .message(1, |m| m.enum_value(1, 2).bool(2, true).enum_value(3, 2))
+                    .message(1, |m| m.enum_value(1, 2).bool(2, false).enum_value(3, 3))
+                    .message(1, |m| m.enum_value(1, 8).bool(2, false).enum_value(3, 3))
+                    .message(1, |m| m.enum_value(1, 10).bool(2, false).enum_value(3, 3))
+                    .message(1, |m| m.enum_value(1, 10).bool(2, true).enum_value(3, 2))
+                    .message(1, |m| m.enum_value(1, 9).bool(2, true).enum_value(3, 2))
+                    .message(1, |m| m.enum_value(1, 10).bool(2, false).enum_value(3, 3))
+                    .message(1, |m| m.enum_value(1, 10).bool(2, true).enum_value(3, 2))
+                    .message(1, |m| m.enum_value(1, 10).bool(2, false).enum_value(3, 4))
+                    .bool(2, true)
+                    .bool(4, true)
+            })
+            .bool(9, false)
+    })
+}
+
+fn place_fields(pb: PbBuilder) -> PbBuilder {
+    pb.message(24, |m| {
+        m.message(1, |m| {
+            m.message(13, |m| {
+                m.bool(2, true)
+                    .bool(3, true)
+                    .bool(4, true)
+                    .int(6, 1)
+                    .bool(8, true)
+                    .bool(9, true)
+                    .bool(14, true)
+                    .bool(20, true)
+                    .bool(25, true)
+            })
+            .message(18, |m| {
+                m.bool(3, true)
+                    .bool(4, true)
+                    .bool(5, true)
+                    .bool(6, true)
+                    .bool(9, true)
+                    .bool(12, true)
+                    .bool(13, true)
+                    .bool(14, true)
+                    .bool(17, true)
+                    .bool(20, true)
+                    .bool(21, true)
+                    .bool(22, true)
+                    .bool(25, true)
+                    .message(27, |m| m.bool(1, false))
+                    .bool(28, false)
+                    .bool(32, false)
+                    .message(33, |m| m.bool(1, true))
+                    .bool(34, false)
+                    .enum_value(36, 1)
+            })
+        })
+        .message(10, |m| m.enum_value(8, 3))
+        .message(11, |m| m.enum_value(3, 1))
+        .message(14, |m| m.bool(3, true))
+        .bool(17, true)
+        .message(20, |m| m.enum_value(1, 3).enum_value(1, 6))
+        .bool(24, true)
+        .bool(25, true)
+        .bool(26, true)
+        .bool(29, true)
+        .message(30, |m| m.bool(2, true))
+        .bool(36, true)
+        .message(39, |m| m.message(2, |m| m.int(2, 1).int(3, 1)))
+        .bool(43, true)
+        .bool(52, true)
+        .message(54, |m| m.bool(1, true))
+        .bool(55, true)
+        .message(56, |m| m.bool(1, true))
+        .message(65, |m| {
+            m.message(3, |m| {
+                m.message(1, |m| m.message(1, |m| m.int(1, 224).int(2, 298)))
+            })
+        })
+        .bool(71, true)
+        .message(72, |m| {
+            m.message(1, |m| {
+                m.bool(1, true)
+                    .bool(2, true)
+                    .bool(3, true)
+                    .bool(5, true)
+                    .bool(7, true)
+            })
+            .bool(4, true)
+            .message(8, |m| {
+                m.message(1, |m| {
+                    m.message(4, |m| m.enum_value(1, 1))
+                        .message(4, |m| m.enum_value(1, 3))
+                        .message(4, |m| m.enum_value(1, 4))
+                })
+                .string(3, "other_user_reviews")
+                .message(6, |m| m.enum_value(1, 1))
+            })
+            .bool(9, true)
+        })
+        .bool(89, true)
+        .message(98, |m| m.bool(1, true).bool(2, true).bool(3, true))
+        .bool(103, true)
+        .bool(113, true)
+        .message(114, |m| m.bool(1, true).message(2, |m| m.bool(1, true)))
+        .bool(117, true)
+        .message(122, |m| m.bool(1, true))
+        .bool(125, false)
+        .bool(126, true)
+        .bool(127, true)
+    })
+    .message(26, |m| m.message(2, |m| m.int(1, 80).int(2, 92).int(4, 8)))
+    .message(30, |m| m)
+    .message(34, |m| {
+        m.bool(2, true)
+            .bool(3, true)
+            .bool(4, true)
+            .bool(6, true)
+            .message(8, |m| {
+                m.bool(1, true)
+                    .bool(3, true)
+                    .bool(4, true)
+                    .bool(5, true)
+                    .bool(6, true)
+                    .bool(7, true)
+            })
+            .bool(9, true)
+            .bool(12, true)
+            .bool(14, true)
+            .bool(20, true)
+            .bool(23, true)
+            .bool(25, true)
+            .bool(26, true)
+    })
+    .message(37, |m| m.enum_value(1, 81))
+    .bool(42, true)
+    .message(47, |m| m)
+    .message(49, |m| {
+        m.bool(3, true)
+            .message(6, |m| m.bool(1, true).bool(2, true))
+            .message(7, |m| m.enum_value(1, 3).bool(2, true))
+            .bool(8, true)
+            .bool(9, true)
+    })
+    .message(50, |m| {
+        m.enum_value(2, 2)
+            .message(3, |m| m.bool(1, true).bool(3, true))
+    })
+    .message(67, |m| m.bool(7, true).bool(10, true))
+    .int(69, 713)
+}
@@ -0,0 +1,291 @@
+use crate::error::AppError;
+use crate::services::google_maps::MapView;
+use crate::services::pb::{PbField, PbValue, SearchPb, SessionTokens};
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+// A difference between two `pb` trees, at a path such as `20.7.1[2].3`.
+// Later repeats of a field number are told apart by their occurrence index.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "lowercase")]
+pub enum PbChange {
+    Added {
+        path: String,
+        value: String,
+    },
+    Removed {
+        path: String,
+        value: String,
+    },
+    Changed {
+        path: String,
+        from: String,
+        to: String,
+    },
+}
+
+impl fmt::Display for PbChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PbChange::Added { path, value } => write!(f, "+ {} {}", path, value),
+            PbChange::Removed { path, value } => write!(f, "- {} {}", path, value),
+            PbChange::Changed { path, from, to } => write!(f, "~ {} {} -> {}", path, from, to),
+        }
+    }
+}
+
+// Parses a captured search URL, or a bare `pb` value starting with `!`.
+pub fn parse_input(input: &str) -> Result<Vec<PbField>, AppError> {
+    let input = input.trim();
+    if input.starts_with('!') {
+        return parse_pb(&percent_decode(input)?);
+    }
+    let query = input.split_once('?').map_or(input, |(_, query)| query);
+    let pb = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("pb="))
+        .ok_or_else(|| invalid("no pb parameter in the URL"))?;
+    parse_pb(&percent_decode(pb)?)
+}
+
+// Parses a decoded `pb` value into its field tree.
+pub fn parse_pb(pb: &str) -> Result<Vec<PbField>, AppError> {
+    let mut tokens = pb.split('!').filter(|token| !token.is_empty());
+    let mut fields = Vec::new();
+    while let Some(token) = tokens.next() {
+        fields.push(parse_field(token, &mut tokens)?);
+    }
+    Ok(fields)
+}
+
+fn parse_field<'a>(
+    token: &'a str,
+    tokens: &mut impl Iterator<Item = &'a str>,
+) -> Result<PbField, AppError> {
+    let split = token
+        .find(|c: char| !c.is_ascii_digit())
+        .filter(|&split| split > 0)
+        .ok_or_else(|| invalid(&format!("field '{}' has no number and type", token)))?;
+    let number = token[..split]
+        .parse()
+        .map_err(|_| invalid(&format!("field number too large in '{}'", token)))?;
+    let letter = token[split..].chars().next().unwrap_or_default();
+    let raw = &token[split + letter.len_utf8()..];
+
+    let number_value = |raw: &str| {
+        raw.parse::<f64>()
+            .map_err(|_| invalid(&format!("invalid number in '{}'", token)))
+    };
+    let int_value = |raw: &str| {
+        raw.parse::<i64>()
+            .map_err(|_| invalid(&format!("invalid integer in '{}'", token)))
+    };
+    let value = match letter {
+        'm' => {
+            let count: usize = raw
+                .parse()
+                .map_err(|_| invalid(&format!("invalid message size in '{}'", token)))?;
+            PbValue::Message(parse_message(count, token, tokens)?)
+        }
+        'd' => PbValue::Double(number_value(raw)?),
+        'f' => PbValue::Float(number_value(raw)?),
+        'i' => PbValue::Int(int_value(raw)?),
+        'e' => PbValue::Enum(int_value(raw)?),
+        'b' => match raw {
+            "0" => PbValue::Bool(false),
+            "1" => PbValue::Bool(true),
+            _ => return Err(invalid(&format!("invalid boolean in '{}'", token))),
+        },
+        's' => PbValue::String(raw.replace("*21", "!").replace("*2A", "*")),
+        other => PbValue::Other(other, raw.to_string()),
+    };
+    Ok(PbField { number, value })
+}
+
+// Reads the fields of a message until `count` descendants have been consumed.
+fn parse_message<'a>(
+    count: usize,
+    token: &str,
+    tokens: &mut impl Iterator<Item = &'a str>,
+) -> Result<Vec<PbField>, AppError> {
+    let mut fields = Vec::new();
+    let mut consumed = 0;
+    while consumed < count {
+        let next = tokens.next().ok_or_else(|| {
+            invalid(&format!(
+                "message '{}' ends after {} of its fields",
+                token, consumed
+            ))
+        })?;
+        let field = parse_field(next, tokens)?;
+        consumed += 1 + field.descendants();
+        fields.push(field);
+    }
+    if consumed > count {
+        return Err(invalid(&format!(
+            "message '{}' overlaps the fields that follow it",
+            token
+        )));
+    }
+    Ok(fields)
+}
+
+// One field per line, nested messages indented below their parent.
+pub fn format_tree(fields: &[PbField]) -> String {
+    let mut output = String::new();
+    write_tree(fields, 0, &mut output);
+    output
+}
+
+fn write_tree(fields: &[PbField], depth: usize, output: &mut String) {
+    for field in fields {
+        output.push_str(&"  ".repeat(depth));
+        output.push_str(&format!("{} {}\n", field.number, describe(&field.value)));
+        if let PbValue::Message(children) = &field.value {
+            write_tree(children, depth + 1, output);
+        }
+    }
+}
+
+// Lists the fields added, removed or changed from `old` to `new`.
+pub fn diff_fields(old: &[PbField], new: &[PbField]) -> Vec<PbChange> {
+    let mut changes = Vec::new();
+    diff_into("", old, new, &mut changes);
+    changes
+}
+
+fn diff_into(prefix: &str, old: &[PbField], new: &[PbField], changes: &mut Vec<PbChange>) {
+    let old_keyed = keyed(prefix, old);
+    let new_keyed = keyed(prefix, new);
+    let new_lookup: HashMap<_, _> = new_keyed.iter().map(|(p, f)| (p.as_str(), *f)).collect();
+    let old_lookup: HashMap<_, _> = old_keyed.iter().map(|(p, f)| (p.as_str(), *f)).collect();
+
+    for (path, old_field) in &old_keyed {
+        match new_lookup.get(path.as_str()) {
+            None => changes.push(PbChange::Removed {
+                path: path.clone(),
+                value: describe(&old_field.value),
+            }),
+            Some(new_field) => match (&old_field.value, &new_field.value) {
+                (PbValue::Message(old_children), PbValue::Message(new_children)) => {
+                    diff_into(path, old_children, new_children, changes)
+                }
+                (old_value, new_value) if old_value != new_value => {
+                    changes.push(PbChange::Changed {
+                        path: path.clone(),
+                        from: describe(old_value),
+                        to: describe(new_value),
+                    })
+                }
+                _ => {}
+            },
+        }
+    }
+    for (path, new_field) in &new_keyed {
+        if !old_lookup.contains_key(path.as_str()) {
+            changes.push(PbChange::Added {
+                path: path.clone(),
+                value: describe(&new_field.value),
+            });
+        }
+    }
+}
+
+// Paths of the fields of one message; repeats of a number get an occurrence index.
+fn keyed<'a>(prefix: &str, fields: &'a [PbField]) -> Vec<(String, &'a PbField)> {
+    let mut seen: HashMap<u32, usize> = HashMap::new();
+    fields
+        .iter()
+        .map(|field| {
+            let index = seen.entry(field.number).or_default();
+            let name = match *index {
+                0 => field.number.to_string(),
+                index => format!("{}[{}]", field.number, index),
+            };
+            *index += 1;
+            let path = if prefix.is_empty() {
+                name
+            } else {
+                format!("{}.{}", prefix, name)
+            };
+            (path, field)
+        })
+        .collect()
+}
+
+// Value at a path of field numbers, following the first field of each number.
+pub fn find_field<'a>(fields: &'a [PbField], path: &[u32]) -> Option<&'a PbValue> {
+    let (first, rest) = path.split_first()?;
+    let field = fields.iter().find(|field| field.number == *first)?;
+    match (&field.value, rest.is_empty()) {
+        (value, true) => Some(value),
+        (PbValue::Message(children), false) => find_field(children, rest),
+        _ => None,
+    }
+}
+
+// The search preset rendered at the position, view and offset of a captured
+// `pb`, so that a diff against it only shows differences in the format.
+pub fn template_like(captured: &[PbField]) -> Vec<PbField> {
+    let number = |path: &[u32]| match find_field(captured, path) {
+        Some(PbValue::Double(value) | PbValue::Float(value)) => Some(*value),
+        Some(PbValue::Int(value)) => Some(*value as f64),
+        _ => None,
+    };
+    let default = MapView::DEFAULT;
+    let view = MapView {
+        span_m: number(&[4, 1, 1]).unwrap_or(default.span_m),
+        width_px: number(&[4, 3, 1]).map_or(default.width_px, |px| px as u32),
+        height_px: number(&[4, 3, 2]).map_or(default.height_px, |px| px as u32),
+        zoom: number(&[4, 4]).unwrap_or(default.zoom),
+    };
+    SearchPb {
+        lat: number(&[4, 1, 3]).unwrap_or_default(),
+        lng: number(&[4, 1, 2]).unwrap_or_default(),
+        view,
+        start: number(&[7]).unwrap_or_default() as u32,
+        offset: number(&[8]).map(|offset| offset as u32),
+        session: SessionTokens::captured(),
+    }
+    .builder()
+    .into_fields()
+}
+
+fn describe(value: &PbValue) -> String {
+    match value {
+        PbValue::Message(fields) => format!("m ({} fields)", fields.len()),
+        PbValue::Double(value) => format!("d {}", value),
+        PbValue::Float(value) => format!("f {}", value),
+        PbValue::Int(value) => format!("i {}", value),
+        PbValue::Bool(value) => format!("b {}", value),
+        PbValue::Enum(value) => format!("e {}", value),
+        PbValue::String(value) => format!("s {:?}", value),
+        PbValue::Other(letter, value) => format!("{} {}", letter, value),
+    }
+}
+
+fn percent_decode(value: &str) -> Result<String, AppError> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| invalid("invalid percent escape"))?;
+            decoded.push(hex);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| invalid("percent escapes are not UTF-8"))
+}
+
+fn invalid(reason: &str) -> AppError {
+    AppError::InvalidPb(reason.to_string())
+}
@@ -0,0 +1,243 @@
+use crate::config::{Locale, PlacesEndpoint};
+use crate::error::AppError;
+use crate::models::text_search::TextSearchResult;
+use crate::models::{Address, Cell, Coordinates, Place, TextSearchResponse};
+use crate::services::search_backend::{PageCursor, SearchBackend, SearchPage};
+
+use crate::utils::http_fetcher::HttpFetcher;
+use crate::utils::proxy_pool::{send_via, ProxyPool, ProxyStats};
+
+use async_trait::async_trait;
+use dotenvy::dotenv;
+use reqwest::header::HeaderMap;
+use std::env;
+use std::sync::Arc;
+use tokio::time::{sleep, Duration};
+use url::Url;
+
+pub const DEFAULT_PLACES_URL: &str = "https://maps.googleapis.com/maps/api/place";
+
+// Results per Places API page
+const PLACES_PAGE_SIZE: usize = 20;
+
+// A next_page_token only becomes valid a short while after it is issued
+const DEFAULT_TOKEN_DELAY: Duration = Duration::from_secs(2);
+
+// Largest search radius the Places API accepts, in metres
+const MAX_RADIUS_M: f64 = 50_000.0;
+
+// The official Places API, searching the circle covering each cell and
+// following `next_page_token` for further pages.
+#[derive(Debug, Clone)]
+pub struct PlacesBackend {
+    fetcher: Arc<dyn HttpFetcher>,
+    api_key: String,
+    endpoint: PlacesEndpoint,
+    base_url: String,
+    locale: Locale,
+    token_delay: Duration,
+    proxies: Option<Arc<ProxyPool>>,
+}
+
+impl PlacesBackend {
+    pub fn new(
+        fetcher: Arc<dyn HttpFetcher>,
+        api_key: impl Into<String>,
+        endpoint: PlacesEndpoint,
+    ) -> Self {
+        PlacesBackend {
+            fetcher,
+            api_key: api_key.into(),
+            endpoint,
+            base_url: DEFAULT_PLACES_URL.to_string(),
+            locale: Locale::default(),
+            token_delay: DEFAULT_TOKEN_DELAY,
+            proxies: None,
+        }
+    }
+
+    // Reads the API key from `GOOGLE_MAPS_API_KEY` in the environment or `.env`.
+    pub fn from_env(
+        fetcher: Arc<dyn HttpFetcher>,
+        endpoint: PlacesEndpoint,
+    ) -> Result<Self, AppError> {
+        dotenv().ok();
+        let api_key = env::var("GOOGLE_MAPS_API_KEY").map_err(|_| AppError::MissingApiKey)?;
+        Ok(Self::new(fetcher, api_key, endpoint))
+    }
+
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    // Wait before requesting a page by token.
+    pub fn token_delay(mut self, token_delay: Duration) -> Self {
+        self.token_delay = token_delay;
+        self
+    }
+
+    // Sends the searches through the proxies of the pool instead of `client`.
+    pub fn proxies(mut self, proxies: Option<Arc<ProxyPool>>) -> Self {
+        self.proxies = proxies;
+        self
+    }
+
+    fn url(&self, cell: &Cell, query: &str, token: Option<&str>) -> Result<Url, AppError> {
+        let path = match self.endpoint {
+            PlacesEndpoint::Text => "textsearch",
+            PlacesEndpoint::Nearby => "nearbysearch",
+        };
+        let mut url = Url::parse(&format!(
+            "{}/{}/json",
+            self.base_url.trim_end_matches('/'),
+            path
+        ))?;
+
+        let mut params = url.query_pairs_mut();
+        match token {
+            Some(token) => {
+                params.append_pair("pagetoken", token);
+            }
+            None => {
+                let (lat, lng) = cell.center();
+                let (height, width) = cell.size_m();
+                let radius = (height.hypot(width) / 2.0).clamp(1.0, MAX_RADIUS_M);
+                let query_param = match self.endpoint {
+                    PlacesEndpoint::Text => "query",
+                    PlacesEndpoint::Nearby => "keyword",
+                };
+                params
+                    .append_pair(query_param, query)
+                    .append_pair("location", &format!("{},{}", lat, lng))
+                    .append_pair("radius", &format!("{:.0}", radius))
+                    .append_pair("language", &self.locale.language);
+                if let Some(region) = &self.locale.region {
+                    params.append_pair("region", region);
+                }
+            }
+        }
+        params.append_pair("key", &self.api_key);
+        drop(params);
+        Ok(url)
+    }
+}
+
+#[async_trait]
+impl SearchBackend for PlacesBackend {
+    async fn search(
+        &self,
+        cell: &Cell,
+        query: &str,
+        cursor: Option<&PageCursor>,
+    ) -> Result<SearchPage, AppError> {
+        let (token, offset) = match cursor {
+            Some(PageCursor::Token { token, offset }) => {
+                sleep(self.token_delay).await;
+                (Some(token.as_str()), *offset)
+            }
+            Some(PageCursor::Offset(_)) => {
+                return Err(AppError::InvalidScraper(
+                    "the Places backend paginates by next_page_token".to_string(),
+                ))
+            }
+            None => (None, 0),
+        };
+        let url = self.url(cell, query, token)?;
+        let (lat, long) = cell.center();
+        log::info!(
+            "Requesting Places {:?} search for lat: {}, long: {}{}",
+            self.endpoint,
+            lat,
+            long,
+            if token.is_some() { " (next page)" } else { "" }
+        );
+
+        let response: TextSearchResponse = send_via(
+            self.proxies.as_deref(),
+            &self.fetcher,
+            cell,
+            |lease| async move {
+                lease
+                    .fetcher
+                    .get(&url, &HeaderMap::new())
+                    .await?
+                    .error_for_status()?
+                    .json()
+            },
+        )
+        .await?;
+        let response = match response.check_status(query) {
+            Err(AppError::ZeroResults(_)) => return Ok(SearchPage::default()),
+            result => result?,
+        };
+
+        let searched_coords = Coordinates {
+            lat: Some(lat),
+            long: Some(long),
+        };
+        let next = response.next_page_token.map(|token| PageCursor::Token {
+            token,
+            offset: offset + response.results.len() as u32,
+        });
+        Ok(SearchPage {
+            places: response
+                .results
+                .into_iter()
+                .map(|result| place_from_result(result, searched_coords.clone()))
+                .collect(),
+            offset,
+            next,
+        })
+    }
+
+    fn page_size(&self) -> usize {
+        PLACES_PAGE_SIZE
+    }
+
+    fn proxy_stats(&self) -> Vec<ProxyStats> {
+        self.proxies
+            .as_ref()
+            .map(|pool| pool.stats())
+            .unwrap_or_default()
+    }
+}
+
+// Maps a Places API result onto the fields the scraper extracts from Maps pages.
+fn place_from_result(result: TextSearchResult, searched_coords: Coordinates) -> Place {
+    Place {
+        address: Address {
+            street_address: result.formatted_address.or(result.vicinity),
+            city: None,
+            zip: None,
+            state: None,
+            country_code: None,
+        },
+        name: result.name,
+        tags: (!result.types.is_empty()).then_some(result.types),
+        notes: None,
+        place_id: result.place_id,
+        phone: None,
+        reviews_count: result.user_ratings_total,
+        avg_reviews: result.rating,
+        reviews_url: None,
+        price_range: result
+            .price_level
+            .filter(|&level| level > 0)
+            .map(|level| "$".repeat(level as usize)),
+        website: None,
+        online_delivery: None,
+        online_delivery_link: None,
+        coordinates: Coordinates {
+            lat: Some(result.geometry.location.lat),
+            long: Some(result.geometry.location.lng),
+        },
+        searched_coords,
+        page_offset: None,
+    }
+}
@@ -0,0 +1,38 @@
+use crate::config::AdaptiveConfig;
+use crate::models::cell::Cell;
+
+use serde::Serialize;
+
+// One searched cell of the plan, with what its search returned.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedCell {
+    pub query: String,
+    #[serde(flatten)]
+    pub cell: Cell,
+    pub requests: usize,
+    pub retries: usize,
+    pub results: usize,
+    pub new_places: usize,
+    // The last page walked was full, so more results are likely
+    pub saturated: bool,
+    pub subdivided: bool,
+}
+
+// Decides whether a searched cell should be split into its four quadrants.
+pub fn should_split(
+    cell: &Cell,
+    saturated: bool,
+    new_places: usize,
+    config: &AdaptiveConfig,
+) -> bool {
+    if cell.depth >= config.max_depth {
+        return false;
+    }
+
+    let (height, width) = cell.size_m();
+    if height.min(width) / 2.0 < config.min_cell_size_m {
+        return false;
+    }
+
+    saturated || new_places >= config.new_places_threshold
+}
@@ -0,0 +1,46 @@
+use url::Url;
+
+// What a Maps search request actually got back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseKind {
+    // The JSON search payload
+    Data,
+    // The cookie consent wall shown to EU visitors
+    ConsentPage,
+    // The `/sorry/` page asking to solve a CAPTCHA after unusual traffic
+    Captcha,
+    // Any other HTML, such as an error or login page
+    UnknownHtml,
+}
+
+// Classifies a response from the URL it ended on after redirects and its body.
+// Body markers are only looked for in HTML, so place names in the JSON payload
+// cannot be mistaken for a block.
+pub fn classify(final_url: &Url, body: &str) -> ResponseKind {
+    if final_url.path().starts_with("/sorry/") {
+        return ResponseKind::Captcha;
+    }
+    if final_url
+        .host_str()
+        .is_some_and(|host| host.starts_with("consent."))
+    {
+        return ResponseKind::ConsentPage;
+    }
+
+    let head = body.get(..4096.min(body.len())).unwrap_or(body);
+    let head_lower = head.to_ascii_lowercase();
+    if !head.trim_start().starts_with('<') && !head_lower.contains("<html") {
+        return ResponseKind::Data;
+    }
+
+    if head_lower.contains("/sorry/index")
+        || head_lower.contains("g-recaptcha")
+        || head_lower.contains("unusual traffic")
+    {
+        ResponseKind::Captcha
+    } else if head_lower.contains("consent.google.") {
+        ResponseKind::ConsentPage
+    } else {
+        ResponseKind::UnknownHtml
+    }
+}
@@ -0,0 +1,224 @@
+use crate::config::{BackendConfig, BrowserConfig, Locale};
+use crate::error::AppError;
+use crate::models::{Cell, Coordinates, Place};
+use crate::services::google_maps::{
+    build_search_url, fetch_places, MapView, PAGE_SIZE, SEARCH_URL,
+};
+use crate::services::pb::SearchPb;
+use crate::services::places_api::{PlacesBackend, DEFAULT_PLACES_URL};
+use crate::services::session::SessionPool;
+use crate::utils::http_fetcher::HttpFetcher;
+use crate::utils::proxy_pool::{send_via, ProxyPool, ProxyStats};
+
+use async_trait::async_trait;
+use std::fmt;
+use std::sync::Arc;
+use url::Url;
+
+// Position in the result pages of one search.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PageCursor {
+    // Number of results skipped
+    Offset(u32),
+    // Token handed out with the previous page, and the offset it leads to
+    Token { token: String, offset: u32 },
+}
+
+// One page of results.
+#[derive(Debug, Default)]
+pub struct SearchPage {
+    pub places: Vec<Place>,
+    // Result offset of the page, recorded on its places
+    pub offset: u32,
+    // Cursor of the following page, `None` on the last one
+    pub next: Option<PageCursor>,
+}
+
+// A source of places for a query around a grid cell.
+#[async_trait]
+pub trait SearchBackend: fmt::Debug + Send + Sync {
+    // Fetches the page at `cursor`, or the first page when it is `None`.
+    async fn search(
+        &self,
+        cell: &Cell,
+        query: &str,
+        cursor: Option<&PageCursor>,
+    ) -> Result<SearchPage, AppError>;
+
+    // Results on a full page; a cell returning that many is saturated.
+    fn page_size(&self) -> usize;
+
+    // Requests served by each proxy, empty when searching without proxies.
+    fn proxy_stats(&self) -> Vec<ProxyStats> {
+        Vec::new()
+    }
+}
+
+// Scrapes the Google Maps web search (`/search?tbm=map`), paginating by offset.
+#[derive(Debug, Clone)]
+pub struct ScrapingBackend {
+    fetcher: Arc<dyn HttpFetcher>,
+    base_url: String,
+    locale: Locale,
+    // Offset of the first page
+    start: u32,
+    proxies: Option<Arc<ProxyPool>>,
+    sessions: Arc<SessionPool>,
+}
+
+impl ScrapingBackend {
+    // Searches with the built-in browser profiles and generated session tokens,
+    // see `sessions` for bootstrapped sessions and other profiles.
+    pub fn new(
+        fetcher: Arc<dyn HttpFetcher>,
+        locale: Locale,
+        start: u32,
+    ) -> Result<Self, AppError> {
+        let browser = BrowserConfig {
+            bootstrap: false,
+            ..BrowserConfig::default()
+        };
+        let sessions = SessionPool::new(&browser, &locale)?;
+        Ok(ScrapingBackend {
+            fetcher,
+            base_url: SEARCH_URL.to_string(),
+            locale,
+            start,
+            proxies: None,
+            sessions: Arc::new(sessions),
+        })
+    }
+
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    // Sends the searches through the proxies of the pool instead of `fetcher`.
+    pub fn proxies(mut self, proxies: Option<Arc<ProxyPool>>) -> Self {
+        self.proxies = proxies;
+        self
+    }
+
+    // Browser profiles and session tokens the searches are sent with.
+    pub fn sessions(mut self, sessions: Arc<SessionPool>) -> Self {
+        self.sessions = sessions;
+        self
+    }
+}
+
+#[async_trait]
+impl SearchBackend for ScrapingBackend {
+    async fn search(
+        &self,
+        cell: &Cell,
+        query: &str,
+        cursor: Option<&PageCursor>,
+    ) -> Result<SearchPage, AppError> {
+        let offset = match cursor {
+            Some(PageCursor::Offset(offset)) => *offset,
+            Some(PageCursor::Token { .. }) => {
+                return Err(AppError::InvalidScraper(
+                    "the scraping backend paginates by offset".to_string(),
+                ))
+            }
+            None => self.start,
+        };
+        let (lat, long) = cell.center();
+        let view = MapView::for_cell(cell)?;
+        let searched_coords = Coordinates {
+            lat: Some(lat),
+            long: Some(long),
+        };
+        // The session is bootstrapped through the proxy its searches go through
+        let places = send_via(
+            self.proxies.as_deref(),
+            &self.fetcher,
+            cell,
+            |lease| async move {
+                let session = self.sessions.session(&lease).await;
+                let pb = SearchPb {
+                    lat,
+                    lng: long,
+                    view,
+                    start: PAGE_SIZE,
+                    offset: (offset > 0).then_some(offset),
+                    session: session.tokens.clone(),
+                };
+                let url = build_search_url(&self.base_url, &pb, query, &self.locale)?;
+                log::info!(
+                    "Requesting offset {} for lat: {}, long: {} with a {:.0} m span (zoom {}, {}x{} px) as {}",
+                    offset,
+                    lat,
+                    long,
+                    view.span_m,
+                    view.zoom,
+                    view.width_px,
+                    view.height_px,
+                    session.profile
+                );
+                let places =
+                    fetch_places(lease.fetcher.as_ref(), &url, &session.headers, searched_coords).await;
+                // A blocked session is not reused, the next request starts a new one
+                if places.as_ref().is_err_and(AppError::is_block) {
+                    self.sessions.invalidate(&session).await;
+                }
+                places
+            },
+        )
+        .await?;
+        Ok(SearchPage {
+            places,
+            offset,
+            next: Some(PageCursor::Offset(offset + PAGE_SIZE)),
+        })
+    }
+
+    fn page_size(&self) -> usize {
+        PAGE_SIZE as usize
+    }
+
+    fn proxy_stats(&self) -> Vec<ProxyStats> {
+        self.proxies
+            .as_ref()
+            .map(|pool| pool.stats())
+            .unwrap_or_default()
+    }
+}
+
+// Creates the backend a job selects, sending its searches through `proxies`
+// when given. The scraping backend rotates the browser profiles of `browser`
+// and bootstraps its sessions through `fetcher`, the Places backend reads its
+// key from `GOOGLE_MAPS_API_KEY`.
+pub fn from_config(
+    config: &BackendConfig,
+    fetcher: Arc<dyn HttpFetcher>,
+    locale: &Locale,
+    start: u32,
+    proxies: Option<Arc<ProxyPool>>,
+    browser: &BrowserConfig,
+) -> Result<Arc<dyn SearchBackend>, AppError> {
+    Ok(match config {
+        BackendConfig::Scraping { base_url } => {
+            // Sessions are bootstrapped from the Maps page of the searched host
+            let mut sessions = SessionPool::new(browser, locale)?;
+            if let Some(base_url) = base_url {
+                sessions = sessions.landing_url(Url::parse(base_url)?.join("/maps")?);
+            }
+            let backend = ScrapingBackend::new(fetcher, locale.clone(), start)?
+                .proxies(proxies)
+                .sessions(Arc::new(sessions));
+            Arc::new(match base_url {
+                Some(base_url) => backend.base_url(base_url),
+                None => backend,
+            })
+        }
+        BackendConfig::Places { endpoint, base_url } => {
+            let backend = PlacesBackend::from_env(fetcher, *endpoint)?
+                .locale(locale.clone())
+                .base_url(base_url.as_deref().unwrap_or(DEFAULT_PLACES_URL))
+                .proxies(proxies);
+            Arc::new(backend)
+        }
+    })
+}
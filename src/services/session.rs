@@ -0,0 +1,367 @@
+use crate::config::{BrowserConfig, Locale};
+use crate::error::AppError;
+use crate::services::pb::SessionTokens;
+use crate::services::response_classifier::{classify, ResponseKind};
+use crate::utils::http_client::profile_headers;
+use crate::utils::http_fetcher::{HttpFetcher, HttpRequest, HttpResponse};
+use crate::utils::proxy_pool::ProxyLease;
+
+use rand::Rng;
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, COOKIE, LOCATION, REFERER, SET_COOKIE};
+use reqwest::{Method, StatusCode};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use url::Url;
+
+// Page a session is bootstrapped from
+pub const DEFAULT_LANDING_URL: &str = "https://www.google.com/maps";
+
+// Redirects followed while loading the Maps page
+const MAX_REDIRECTS: usize = 10;
+
+// Places the `ei` token appears in the Maps page source
+const EI_MARKERS: [&str; 4] = ["kEI='", "kEI:'", "\"kEI\":\"", "\"ei\":\""];
+
+// One browser identity: the headers of a profile, with the cookies of the
+// session, and the tokens of its session.
+#[derive(Debug)]
+pub struct BrowserSession {
+    pub profile: String,
+    pub headers: HeaderMap,
+    pub tokens: SessionTokens,
+    route: Option<usize>,
+    started: Instant,
+}
+
+// Hands out the current session and starts a new one, with the next profile,
+// when it has served `session_requests` requests, outlived its time to live or
+// been invalidated. New sessions are bootstrapped from the Maps page.
+// Every proxy, and direct requests, has sessions of its own, bootstrapped
+// through it, so cookies and tokens stay with the address that obtained them.
+// A bootstrap only holds up the requests of its own route.
+#[derive(Debug)]
+pub struct SessionPool {
+    profiles: Vec<(String, HeaderMap)>,
+    session_requests: u32,
+    ttl: Duration,
+    bootstrap: bool,
+    landing_url: Url,
+    locale: Locale,
+    // Keyed by the index of the proxy in its pool, `None` for direct requests
+    routes: Mutex<HashMap<Option<usize>, Arc<tokio::sync::Mutex<Route>>>>,
+    next_profile: AtomicUsize,
+}
+
+#[derive(Debug, Default)]
+struct Route {
+    current: Option<Arc<BrowserSession>>,
+    requests: u32,
+}
+
+// What loading the Maps page yielded.
+#[derive(Debug)]
+struct Bootstrap {
+    tokens: SessionTokens,
+    cookie: Option<HeaderValue>,
+}
+
+// A `<form>` of an HTML page with the values of its inputs.
+#[derive(Debug)]
+struct HtmlForm {
+    action: String,
+    fields: Vec<(String, String)>,
+}
+
+impl SessionPool {
+    // Parses the headers of every selected profile up front, so a bad header
+    // fails the run before any request is sent.
+    pub fn new(config: &BrowserConfig, locale: &Locale) -> Result<Self, AppError> {
+        let profiles = config
+            .resolve()?
+            .iter()
+            .map(|profile| Ok((profile.name.clone(), profile_headers(profile, locale)?)))
+            .collect::<Result<Vec<_>, AppError>>()?;
+
+        // Runs start on a random profile so they do not all look alike
+        let first = rand::thread_rng().gen_range(0..profiles.len());
+        Ok(SessionPool {
+            profiles,
+            session_requests: config.session_requests.max(1),
+            ttl: Duration::from_secs(config.session_ttl_secs),
+            bootstrap: config.bootstrap,
+            landing_url: Url::parse(DEFAULT_LANDING_URL)
+                .expect("DEFAULT_LANDING_URL is a valid URL"),
+            locale: locale.clone(),
+            routes: Mutex::new(HashMap::new()),
+            next_profile: AtomicUsize::new(first),
+        })
+    }
+
+    // Page sessions are bootstrapped from, such as a local mock server.
+    pub fn landing_url(mut self, landing_url: Url) -> Self {
+        self.landing_url = landing_url;
+        self
+    }
+
+    // The session the next request is sent with, starting a new one if needed.
+    // New sessions are bootstrapped through the proxy of `lease`.
+    pub async fn session(&self, lease: &ProxyLease) -> Arc<BrowserSession> {
+        let key = lease.proxy();
+        let route = Arc::clone(self.routes.lock().unwrap().entry(key).or_default());
+        let mut route = route.lock().await;
+        let expired = route.current.as_ref().is_none_or(|session| {
+            route.requests >= self.session_requests || session.started.elapsed() >= self.ttl
+        });
+        if expired {
+            let next = self.next_profile.fetch_add(1, Ordering::Relaxed) % self.profiles.len();
+            let session = self
+                .start_session(key, lease.fetcher.as_ref(), &self.profiles[next])
+                .await;
+            *route = Route {
+                current: Some(session),
+                requests: 0,
+            };
+        }
+        route.requests += 1;
+        Arc::clone(route.current.as_ref().expect("a session was just started"))
+    }
+
+    // Ends a session Google no longer accepts, so the next request starts a new
+    // one. Does nothing if the session was already replaced.
+    pub async fn invalidate(&self, session: &Arc<BrowserSession>) {
+        let Some(route) = self.routes.lock().unwrap().get(&session.route).cloned() else {
+            return;
+        };
+        let mut route = route.lock().await;
+        if route
+            .current
+            .as_ref()
+            .is_some_and(|current| Arc::ptr_eq(current, session))
+        {
+            log::info!("Dropping the {} session", session.profile);
+            route.current = None;
+        }
+    }
+
+    async fn start_session(
+        &self,
+        route: Option<usize>,
+        fetcher: &dyn HttpFetcher,
+        (profile, headers): &(String, HeaderMap),
+    ) -> Arc<BrowserSession> {
+        let mut headers = headers.clone();
+        let tokens = if self.bootstrap {
+            match bootstrap(fetcher, &self.landing_url, &self.locale, &headers).await {
+                Ok(bootstrap) => {
+                    if let Some(cookie) = bootstrap.cookie {
+                        headers.insert(COOKIE, cookie);
+                    }
+                    bootstrap.tokens
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Could not bootstrap a {} session, using generated tokens: {}",
+                        profile,
+                        e
+                    );
+                    SessionTokens::generate()
+                }
+            }
+        } else {
+            SessionTokens::generate()
+        };
+
+        log::info!("Starting a new session as {} (ei {})", profile, tokens.ei);
+        Arc::new(BrowserSession {
+            profile: profile.clone(),
+            headers,
+            tokens,
+            route,
+            started: Instant::now(),
+        })
+    }
+}
+
+// Loads the Maps page with a cookie jar of its own, answers the consent wall
+// when redirected to it, and reads the `ei` token from the page.
+async fn bootstrap(
+    fetcher: &dyn HttpFetcher,
+    landing_url: &Url,
+    locale: &Locale,
+    headers: &HeaderMap,
+) -> Result<Bootstrap, AppError> {
+    let jar = Jar::default();
+
+    // The landing page is navigated to, not fetched from a script
+    let mut page_headers = headers.clone();
+    page_headers.remove(REFERER);
+    page_headers.insert(
+        ACCEPT,
+        HeaderValue::from_static("text/html,application/xhtml+xml,*/*;q=0.8"),
+    );
+    page_headers.insert("sec-fetch-dest", HeaderValue::from_static("document"));
+    page_headers.insert("sec-fetch-mode", HeaderValue::from_static("navigate"));
+    page_headers.insert("sec-fetch-site", HeaderValue::from_static("none"));
+
+    let mut url = landing_url.clone();
+    url.query_pairs_mut().append_pair("hl", &locale.language);
+    if let Some(region) = &locale.region {
+        url.query_pairs_mut().append_pair("gl", region);
+    }
+
+    let loaded_at = SystemTime::now();
+    let request = HttpRequest::get(url, page_headers.clone());
+    let response = navigate(fetcher, &jar, request).await?.error_for_status()?;
+    let mut page_url = response.url;
+    let mut html = response.body;
+
+    if classify(&page_url, &html) == ResponseKind::ConsentPage {
+        let form =
+            consent_form(&html).ok_or_else(|| AppError::ConsentPage(page_url.to_string()))?;
+        let action = page_url.join(&form.action)?;
+        log::info!("Answering the consent page at {}", action);
+        let request = HttpRequest {
+            method: Method::POST,
+            url: action,
+            headers: page_headers,
+            form: Some(form.fields),
+        };
+        let response = navigate(fetcher, &jar, request).await?.error_for_status()?;
+        page_url = response.url;
+        html = response.body;
+    }
+
+    match classify(&page_url, &html) {
+        ResponseKind::ConsentPage => return Err(AppError::ConsentPage(page_url.into())),
+        ResponseKind::Captcha => return Err(AppError::CaptchaPage(page_url.into())),
+        _ => {}
+    }
+
+    let ei = extract_ei(&html)
+        .ok_or_else(|| AppError::SessionBootstrap("no ei token in the Maps page".to_string()))?;
+    let timestamp_ms = loaded_at
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    Ok(Bootstrap {
+        tokens: SessionTokens { ei, timestamp_ms },
+        cookie: jar.cookies(landing_url),
+    })
+}
+
+// Sends a request and follows its redirects as a browser would, sending and
+// keeping the cookies of `jar` along the way.
+async fn navigate(
+    fetcher: &dyn HttpFetcher,
+    jar: &Jar,
+    mut request: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    for _ in 0..=MAX_REDIRECTS {
+        match jar.cookies(&request.url) {
+            Some(cookie) => request.headers.insert(COOKIE, cookie),
+            None => request.headers.remove(COOKIE),
+        };
+        let response = fetcher.send(request.clone()).await?;
+        for cookie in response.headers.get_all(SET_COOKIE) {
+            if let Ok(cookie) = cookie.to_str() {
+                jar.add_cookie_str(cookie, &response.url);
+            }
+        }
+
+        let location = response
+            .headers
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok());
+        let Some(location) = location.filter(|_| response.status.is_redirection()) else {
+            return Ok(response);
+        };
+        request.url = response.url.join(location)?;
+        // Only 307 and 308 keep a posted form
+        let keeps_method = matches!(
+            response.status,
+            StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT
+        );
+        if !keeps_method {
+            request.method = Method::GET;
+            request.form = None;
+        }
+    }
+    Err(AppError::SessionBootstrap(format!(
+        "more than {} redirects",
+        MAX_REDIRECTS
+    )))
+}
+
+// The `ei` event id Google embeds in every page it serves.
+fn extract_ei(html: &str) -> Option<String> {
+    EI_MARKERS.iter().find_map(|marker| {
+        let start = html.find(marker)? + marker.len();
+        let ei: String = html[start..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+            .collect();
+        (!ei.is_empty()).then_some(ei)
+    })
+}
+
+// The form saving the consent choice, rejecting optional cookies when the page
+// offers that choice.
+fn consent_form(html: &str) -> Option<HtmlForm> {
+    let forms: Vec<HtmlForm> = html_forms(html)
+        .into_iter()
+        .filter(|form| form.action.contains("/save"))
+        .collect();
+    let rejects = |form: &HtmlForm| {
+        form.fields
+            .iter()
+            .any(|(name, value)| name == "set_eom" && value == "true")
+    };
+    let index = forms.iter().position(rejects).unwrap_or(0);
+    forms.into_iter().nth(index)
+}
+
+// Every form of a page with its action and its named inputs.
+fn html_forms(html: &str) -> Vec<HtmlForm> {
+    html.split("<form")
+        .skip(1)
+        .map(|part| {
+            let body = part.split("</form>").next().unwrap_or(part);
+            let tag = body.split('>').next().unwrap_or(body);
+            let fields = body
+                .split("<input")
+                .skip(1)
+                .filter_map(|input| {
+                    let tag = input.split('>').next().unwrap_or(input);
+                    let name = attribute(tag, "name")?;
+                    Some((name, attribute(tag, "value").unwrap_or_default()))
+                })
+                .collect();
+            HtmlForm {
+                action: attribute(tag, "action").unwrap_or_default(),
+                fields,
+            }
+        })
+        .collect()
+}
+
+// Value of a quoted attribute in the text of a tag, with entities decoded.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    ['"', '\''].iter().find_map(|&quote| {
+        let marker = format!(" {}={}", name, quote);
+        let start = tag.find(&marker)? + marker.len();
+        let end = tag[start..].find(quote)? + start;
+        Some(decode_entities(&tag[start..end]))
+    })
+}
+
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,165 @@
+use crate::config::{GeocoderConfig, RetryPolicy};
+use crate::error::AppError;
+use crate::models::{MultiPolygon, Viewport};
+use crate::services::gazetteer::Gazetteer;
+use crate::services::google_maps::search_location;
+use crate::utils::http_fetcher::HttpFetcher;
+
+use async_trait::async_trait;
+use dotenvy::dotenv;
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde_json::Value;
+use std::env;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use url::Url;
+
+// Public Nominatim instance, subject to its usage policy of one request per second
+pub const DEFAULT_NOMINATIM_URL: &str = "https://nominatim.openstreetmap.org";
+
+// Nominatim rejects requests without an identifying user agent
+const USER_AGENT: &str = concat!("google_maps/", env!("CARGO_PKG_VERSION"));
+
+// Resolves an area name to the viewport whose grid is searched.
+#[async_trait]
+pub trait ViewportProvider: fmt::Debug + Send + Sync {
+    async fn viewport(&self, area: &str) -> Result<Viewport, AppError>;
+}
+
+// The paid Places Text Search API, the first result's viewport.
+#[derive(Debug, Clone)]
+pub struct GoogleTextSearch {
+    fetcher: Arc<dyn HttpFetcher>,
+    api_key: String,
+    retry: RetryPolicy,
+}
+
+// A Nominatim-compatible `/search` endpoint, the first result's bounding box.
+#[derive(Debug, Clone)]
+pub struct Nominatim {
+    fetcher: Arc<dyn HttpFetcher>,
+    base_url: String,
+}
+
+// A fixed viewport; the area name is ignored.
+#[derive(Debug, Clone)]
+pub struct BoundingBox(pub Viewport);
+
+// The bounding box of a GeoJSON or WKT polygon file; the area name is ignored.
+#[derive(Debug, Clone)]
+pub struct PolygonFile(pub PathBuf);
+
+impl GoogleTextSearch {
+    pub fn new(fetcher: Arc<dyn HttpFetcher>, api_key: impl Into<String>) -> Self {
+        GoogleTextSearch {
+            fetcher,
+            api_key: api_key.into(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    // Retries quota and server errors with the policy of the job instead of the default one.
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    // Reads the API key from `GOOGLE_MAPS_API_KEY` in the environment or `.env`.
+    pub fn from_env(fetcher: Arc<dyn HttpFetcher>) -> Result<Self, AppError> {
+        dotenv().ok();
+        let api_key = env::var("GOOGLE_MAPS_API_KEY").map_err(|_| AppError::MissingApiKey)?;
+        Ok(Self::new(fetcher, api_key))
+    }
+}
+
+impl Nominatim {
+    pub fn new(fetcher: Arc<dyn HttpFetcher>, base_url: impl Into<String>) -> Self {
+        Nominatim {
+            fetcher,
+            base_url: base_url.into(),
+        }
+    }
+
+    fn search_url(&self, area: &str) -> Result<Url, AppError> {
+        let base = format!("{}/search", self.base_url.trim_end_matches('/'));
+        let params = [("q", area), ("format", "jsonv2"), ("limit", "1")];
+        Ok(Url::parse_with_params(&base, &params)?)
+    }
+}
+
+#[async_trait]
+impl ViewportProvider for GoogleTextSearch {
+    async fn viewport(&self, area: &str) -> Result<Viewport, AppError> {
+        log::info!("Starting search for location: {}", area);
+        search_location(self.fetcher.as_ref(), &self.api_key, area, &self.retry)
+            .await?
+            .viewport()
+    }
+}
+
+#[async_trait]
+impl ViewportProvider for Nominatim {
+    async fn viewport(&self, area: &str) -> Result<Viewport, AppError> {
+        let url = self.search_url(area)?;
+        log::info!("Geocoding {} through {}", area, self.base_url);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            reqwest::header::USER_AGENT,
+            HeaderValue::from_static(USER_AGENT),
+        );
+        let json: Value = self
+            .fetcher
+            .get(&url, &headers)
+            .await?
+            .error_for_status()?
+            .json()?;
+        Viewport::from_nominatim(&json)
+    }
+}
+
+#[async_trait]
+impl ViewportProvider for BoundingBox {
+    async fn viewport(&self, _area: &str) -> Result<Viewport, AppError> {
+        Ok(self.0.clone())
+    }
+}
+
+#[async_trait]
+impl ViewportProvider for PolygonFile {
+    async fn viewport(&self, _area: &str) -> Result<Viewport, AppError> {
+        Ok(MultiPolygon::load(&self.0)?.viewport())
+    }
+}
+
+// Creates the provider a job or command line selects. `retry` applies to the
+// Text Search requests.
+pub fn from_config(
+    config: &GeocoderConfig,
+    fetcher: Arc<dyn HttpFetcher>,
+    retry: &RetryPolicy,
+) -> Result<Arc<dyn ViewportProvider>, AppError> {
+    Ok(match config {
+        GeocoderConfig::Google => {
+            Arc::new(GoogleTextSearch::from_env(fetcher)?.retry(retry.clone()))
+        }
+        GeocoderConfig::Nominatim { url } => Arc::new(Nominatim::new(fetcher, url)),
+        GeocoderConfig::Bbox {
+            south,
+            west,
+            north,
+            east,
+        } => Arc::new(BoundingBox(Viewport {
+            northeast: (*north, *east),
+            southwest: (*south, *west),
+        })),
+        GeocoderConfig::Polygon { path } => Arc::new(PolygonFile(path.clone())),
+        GeocoderConfig::Gazetteer { path, country } => {
+            let gazetteer = match path {
+                Some(path) => Gazetteer::load(path)?,
+                None => Gazetteer::bundled(),
+            };
+            Arc::new(gazetteer.country(country.clone()))
+        }
+    })
+}
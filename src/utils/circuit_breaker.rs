@@ -0,0 +1,98 @@
+use crate::config::{BreakerAction, CircuitBreakerConfig};
+use crate::error::AppError;
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::time::{sleep_until, Duration, Instant};
+
+// Watches the share of blocked responses among the last requests of a run and
+// pauses or aborts the run once it crosses the configured threshold.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: Arc<Mutex<BreakerState>>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    // Whether each recent request was blocked, oldest first
+    outcomes: VecDeque<bool>,
+    open_until: Option<Instant>,
+    pauses: u32,
+    // Reason the run was aborted
+    tripped: Option<String>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        CircuitBreaker {
+            config,
+            state: Arc::new(Mutex::new(BreakerState::default())),
+        }
+    }
+
+    // Waits out a pause, or fails once the run has been aborted.
+    pub async fn wait(&self) -> Result<(), AppError> {
+        loop {
+            let open_until = {
+                let state = self.state.lock().unwrap();
+                if let Some(reason) = &state.tripped {
+                    return Err(AppError::CircuitOpen(reason.clone()));
+                }
+                state.open_until
+            };
+            match open_until {
+                Some(until) if until > Instant::now() => sleep_until(until).await,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    // Records the outcome of one request and opens the circuit when needed.
+    pub fn record(&self, blocked: bool) {
+        if !self.config.enabled {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.tripped.is_some() || state.open_until.is_some_and(|t| t > Instant::now()) {
+            return;
+        }
+        state.outcomes.push_back(blocked);
+        while state.outcomes.len() > self.config.window {
+            state.outcomes.pop_front();
+        }
+
+        let total = state.outcomes.len();
+        let blocks = state.outcomes.iter().filter(|&&b| b).count();
+        let block_rate = blocks as f64 / total as f64;
+        if total < self.config.min_requests || block_rate < self.config.block_threshold {
+            return;
+        }
+
+        let reason = format!("{} of the last {} requests were blocked", blocks, total);
+        match self.config.action {
+            BreakerAction::Pause if state.pauses < self.config.max_pauses => {
+                state.pauses += 1;
+                state.outcomes.clear();
+                state.open_until =
+                    Some(Instant::now() + Duration::from_secs(self.config.pause_secs));
+                log::warn!(
+                    "{}, pausing requests for {} s (pause {}/{})",
+                    reason,
+                    self.config.pause_secs,
+                    state.pauses,
+                    self.config.max_pauses
+                );
+            }
+            _ => {
+                log::error!("{}, aborting the run", reason);
+                state.tripped = Some(reason);
+            }
+        }
+    }
+
+    // Reason the run was aborted, if it was.
+    pub fn tripped(&self) -> Option<String> {
+        self.state.lock().unwrap().tripped.clone()
+    }
+}
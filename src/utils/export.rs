@@ -0,0 +1,122 @@
+use crate::error::AppError;
+use crate::models::place::Place;
+use crate::services::quadtree::PlannedCell;
+use crate::utils::json_utils::save_places;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Json,
+    Csv,
+}
+
+const CSV_HEADER: [&str; 21] = [
+    "place_id",
+    "name",
+    "tags",
+    "notes",
+    "phone",
+    "website",
+    "reviews_count",
+    "avg_reviews",
+    "reviews_url",
+    "price_range",
+    "online_delivery",
+    "online_delivery_link",
+    "street_address",
+    "city",
+    "zip",
+    "state",
+    "country_code",
+    "lat",
+    "long",
+    "searched_coords",
+    "page_offset",
+];
+
+// Writes places to `path` in the requested format.
+pub fn save_places_as(places: &[Place], path: &Path, format: OutputFormat) -> Result<(), AppError> {
+    match format {
+        OutputFormat::Json => save_places(places, path),
+        OutputFormat::Csv => save_places_csv(places, path),
+    }
+}
+
+// Writes the searched cells of a run as JSON.
+pub fn save_plan(plan: &[PlannedCell], path: &Path) -> Result<(), AppError> {
+    fs::write(path, serde_json::to_string_pretty(plan)?)?;
+    Ok(())
+}
+
+// Writes places as CSV, one row per place with the address and coordinates flattened.
+pub fn save_places_csv(places: &[Place], path: &Path) -> Result<(), AppError> {
+    let mut output = csv_row(CSV_HEADER.iter().map(|s| s.to_string()));
+    for place in places {
+        output.push_str(&csv_row(place_record(place)));
+    }
+    fs::write(path, output)?;
+    Ok(())
+}
+
+fn place_record(place: &Place) -> Vec<String> {
+    let text = |value: &Option<String>| value.clone().unwrap_or_default();
+    let number = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+
+    vec![
+        text(&place.place_id),
+        text(&place.name),
+        place
+            .tags
+            .as_ref()
+            .map(|t| t.join("; "))
+            .unwrap_or_default(),
+        text(&place.notes),
+        text(&place.phone),
+        text(&place.website),
+        place
+            .reviews_count
+            .map(|v| v.to_string())
+            .unwrap_or_default(),
+        number(place.avg_reviews),
+        text(&place.reviews_url),
+        text(&place.price_range),
+        text(&place.online_delivery),
+        text(&place.online_delivery_link),
+        text(&place.address.street_address),
+        text(&place.address.city),
+        text(&place.address.zip),
+        text(&place.address.state),
+        text(&place.address.country_code),
+        number(place.coordinates.lat),
+        number(place.coordinates.long),
+        match (place.searched_coords.lat, place.searched_coords.long) {
+            (Some(lat), Some(long)) => format!("{},{}", lat, long),
+            _ => String::new(),
+        },
+        place.page_offset.map(|v| v.to_string()).unwrap_or_default(),
+    ]
+}
+
+fn csv_row(fields: impl IntoIterator<Item = String>) -> String {
+    let mut row = fields
+        .into_iter()
+        .map(|field| csv_escape(&field))
+        .collect::<Vec<_>>()
+        .join(",");
+    row.push('\n');
+    row
+}
+
+// Quotes a field if it contains a separator, a quote or a line break.
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -1,34 +1,174 @@
-use reqwest::header;
-
-pub fn create_headers() -> header::HeaderMap {
-    let mut headers = header::HeaderMap::new();
-    headers.insert("accept", "*/*".parse().unwrap());
-    headers.insert("accept-language", "en-US,en;q=0.7".parse().unwrap());
-    headers.insert("priority", "u=1, i".parse().unwrap());
-    headers.insert("referer", "https://www.google.com/".parse().unwrap());
-    headers.insert(
-        "sec-ch-ua",
-        "\"Brave\";v=\"131\", \"Chromium\";v=\"131\", \"Not_A Brand\";v=\"24\""
-            .parse()
-            .unwrap(),
-    );
-    headers.insert("sec-ch-ua-arch", "\"x86\"".parse().unwrap());
-    headers.insert("sec-ch-ua-bitness", "\"64\"".parse().unwrap());
-    headers.insert(
-        "sec-ch-ua-full-version-list",
-        "\"Brave\";v=\"131.0.0.0\", \"Chromium\";v=\"131.0.0.0\", \"Not_A Brand\";v=\"24.0.0.0\""
-            .parse()
-            .unwrap(),
-    );
-    headers.insert("sec-ch-ua-mobile", "?0".parse().unwrap());
-    headers.insert("sec-ch-ua-model", "\"\"".parse().unwrap());
-    headers.insert("sec-ch-ua-platform", "\"Linux\"".parse().unwrap());
-    headers.insert("sec-ch-ua-platform-version", "\"6.8.0\"".parse().unwrap());
-    headers.insert("sec-ch-ua-wow64", "?0".parse().unwrap());
-    headers.insert("sec-fetch-dest", "empty".parse().unwrap());
-    headers.insert("sec-fetch-mode", "cors".parse().unwrap());
-    headers.insert("sec-fetch-site", "same-origin".parse().unwrap());
-    headers.insert("sec-gpc", "1".parse().unwrap());
-    headers.insert("user-agent", "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36".parse().unwrap());
-    headers
+use crate::config::{BrowserProfile, HttpConfig, HttpVersion, Locale};
+use crate::error::AppError;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Certificate, Client, ClientBuilder};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+// Names of the profiles available without configuration
+pub const BUILTIN_PROFILES: [&str; 4] = ["chrome", "firefox", "safari", "mobile"];
+
+// Sent by every browser for a same-origin `fetch` from the Maps page
+const COMMON_HEADERS: [(&str, &str); 5] = [
+    ("accept", "*/*"),
+    ("referer", "https://www.google.com/"),
+    ("sec-fetch-dest", "empty"),
+    ("sec-fetch-mode", "cors"),
+    ("sec-fetch-site", "same-origin"),
+];
+
+const CHROME_UA: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+    (KHTML, like Gecko) Chrome/141.0.0.0 Safari/537.36";
+const CHROME_BRANDS: &str =
+    "\"Google Chrome\";v=\"141\", \"Not?A_Brand\";v=\"8\", \"Chromium\";v=\"141\"";
+const FIREFOX_UA: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:144.0) Gecko/20100101 Firefox/144.0";
+const SAFARI_UA: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 \
+    (KHTML, like Gecko) Version/18.6 Safari/605.1.15";
+const MOBILE_UA: &str = "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 \
+    (KHTML, like Gecko) Chrome/141.0.0.0 Mobile Safari/537.36";
+
+// A built-in profile by name. Only Chromium browsers send `sec-ch-ua` client hints.
+pub fn builtin_profile(name: &str) -> Option<BrowserProfile> {
+    let (user_agent, headers): (&str, &[(&str, &str)]) = match name {
+        "chrome" => (
+            CHROME_UA,
+            &[
+                ("priority", "u=1, i"),
+                ("sec-ch-ua", CHROME_BRANDS),
+                ("sec-ch-ua-mobile", "?0"),
+                ("sec-ch-ua-platform", "\"Windows\""),
+            ],
+        ),
+        "firefox" => (FIREFOX_UA, &[("priority", "u=4")]),
+        "safari" => (SAFARI_UA, &[("priority", "u=3, i")]),
+        "mobile" => (
+            MOBILE_UA,
+            &[
+                ("priority", "u=1, i"),
+                ("sec-ch-ua", CHROME_BRANDS),
+                ("sec-ch-ua-mobile", "?1"),
+                ("sec-ch-ua-platform", "\"Android\""),
+            ],
+        ),
+        _ => return None,
+    };
+    Some(BrowserProfile {
+        name: name.to_string(),
+        user_agent: user_agent.to_string(),
+        accept_language: None,
+        headers: headers
+            .iter()
+            .map(|&(name, value)| (name.to_string(), value.to_string()))
+            .collect::<BTreeMap<_, _>>(),
+    })
+}
+
+// `Accept-Language` matching the search locale, such as `ur-PK,ur;q=0.9,en;q=0.8`.
+pub fn accept_language(locale: &Locale) -> String {
+    let language = locale.language.trim();
+    let base = language.split('-').next().unwrap_or(language);
+    let primary = match &locale.region {
+        Some(region) if !language.contains('-') => {
+            format!("{}-{}", language, region.to_ascii_uppercase())
+        }
+        _ => language.to_string(),
+    };
+
+    let mut ranges = vec![primary.clone()];
+    if primary != base {
+        ranges.push(format!("{};q=0.9", base));
+    }
+    if base != "en" {
+        ranges.push("en;q=0.8".to_string());
+    }
+    ranges.join(",")
+}
+
+// Every header a profile sends: the common ones, its user agent and language,
+// then its own headers, which override the common ones.
+pub fn profile_headers(profile: &BrowserProfile, locale: &Locale) -> Result<HeaderMap, AppError> {
+    let accept_language = profile
+        .accept_language
+        .clone()
+        .unwrap_or_else(|| accept_language(locale));
+
+    let mut headers = HeaderMap::new();
+    let profile_headers = profile
+        .headers
+        .iter()
+        .map(|(n, v)| (n.as_str(), v.as_str()));
+    for (name, value) in COMMON_HEADERS
+        .into_iter()
+        .chain([
+            ("user-agent", profile.user_agent.as_str()),
+            ("accept-language", accept_language.as_str()),
+        ])
+        .chain(profile_headers)
+    {
+        headers.insert(
+            parse_header_name(&profile.name, name)?,
+            parse_header_value(&profile.name, name, value)?,
+        );
+    }
+    Ok(headers)
+}
+
+fn parse_header_name(profile: &str, name: &str) -> Result<HeaderName, AppError> {
+    HeaderName::from_bytes(name.as_bytes()).map_err(|_| AppError::InvalidHeader {
+        profile: profile.to_string(),
+        header: name.to_string(),
+    })
+}
+
+fn parse_header_value(profile: &str, name: &str, value: &str) -> Result<HeaderValue, AppError> {
+    HeaderValue::from_str(value).map_err(|_| AppError::InvalidHeader {
+        profile: profile.to_string(),
+        header: name.to_string(),
+    })
+}
+
+// A client builder with the timeouts, pooling, compression, protocol version,
+// extra root certificates and local address of the configuration. Callers add
+// their own proxy or cookie store before building it.
+pub fn client_builder(config: &HttpConfig) -> Result<ClientBuilder, AppError> {
+    let mut builder = Client::builder()
+        .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+        .read_timeout(Duration::from_secs(config.read_timeout_secs))
+        .timeout(Duration::from_secs(config.timeout_secs))
+        .pool_max_idle_per_host(config.pool_max_idle_per_host)
+        .gzip(config.gzip)
+        .brotli(config.brotli)
+        .local_address(config.bind_address);
+    builder = match config.version {
+        HttpVersion::Auto => builder,
+        HttpVersion::Http1 => builder.http1_only(),
+        HttpVersion::Http2 => builder.http2_prior_knowledge(),
+    };
+    if let Some(path) = &config.ca_bundle {
+        for certificate in load_ca_bundle(path)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    Ok(builder)
+}
+
+pub fn build_client(config: &HttpConfig) -> Result<Client, AppError> {
+    Ok(client_builder(config)?.build()?)
+}
+
+// Every certificate of a PEM file, which must hold at least one.
+pub fn load_ca_bundle(path: &Path) -> Result<Vec<Certificate>, AppError> {
+    let invalid = |reason: String| AppError::InvalidCaBundle {
+        path: path.display().to_string(),
+        reason,
+    };
+    let pem = fs::read(path).map_err(|e| invalid(e.to_string()))?;
+    let certificates = Certificate::from_pem_bundle(&pem).map_err(|e| invalid(e.to_string()))?;
+    if certificates.is_empty() {
+        return Err(invalid("no PEM certificate found".to_string()));
+    }
+    Ok(certificates)
 }
@@ -0,0 +1,259 @@
+use crate::config::HttpConfig;
+use crate::error::AppError;
+use crate::utils::http_client::client_builder;
+use crate::utils::retry::retry_after;
+
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use reqwest::redirect::Policy;
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use url::Url;
+
+// A single request, sent as is: its redirects are answered, not followed.
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: Url,
+    pub headers: HeaderMap,
+    // Fields sent url-encoded as the body
+    pub form: Option<Vec<(String, String)>>,
+}
+
+// A response read to the end: its status, the URL after redirects, its
+// headers and its body.
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub url: Url,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+// Sends the requests of the pipeline. The reqwest implementation goes to the
+// network, the fixture one answers from memory so tests run offline.
+#[async_trait]
+pub trait HttpFetcher: fmt::Debug + Send + Sync {
+    // GET following redirects.
+    async fn get(&self, url: &Url, headers: &HeaderMap) -> Result<HttpResponse, AppError>;
+
+    // Any request, without following redirects, so cookies set along them can
+    // be kept.
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, AppError>;
+
+    // The same fetcher tunnelling its requests through `proxy`.
+    fn via_proxy(&self, proxy: &Url) -> Result<Arc<dyn HttpFetcher>, AppError>;
+}
+
+impl HttpRequest {
+    pub fn get(url: Url, headers: HeaderMap) -> Self {
+        HttpRequest {
+            method: Method::GET,
+            url,
+            headers,
+            form: None,
+        }
+    }
+}
+
+impl HttpResponse {
+    // Wait requested by the server through `Retry-After`.
+    pub fn retry_after(&self) -> Option<Duration> {
+        retry_after(&self.headers)
+    }
+
+    // Turns a non-2xx response into `AppError::HttpStatus`, keeping its `Retry-After`.
+    pub fn error_for_status(self) -> Result<Self, AppError> {
+        if self.status.is_success() {
+            return Ok(self);
+        }
+        Err(AppError::HttpStatus {
+            status: self.status.as_u16(),
+            retry_after: self.retry_after(),
+        })
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, AppError> {
+        Ok(serde_json::from_str(&self.body)?)
+    }
+}
+
+// Sends the requests with reqwest clients built from an `HttpConfig`, or with
+// a given client for GETs and default settings for the rest.
+#[derive(Debug, Clone)]
+pub struct ReqwestFetcher {
+    client: reqwest::Client,
+    // Same settings without following redirects, for `send`
+    single: reqwest::Client,
+    http: HttpConfig,
+}
+
+impl ReqwestFetcher {
+    pub fn new(client: reqwest::Client) -> Self {
+        let http = HttpConfig::default();
+        let single = client_builder(&http)
+            .and_then(|builder| Ok(builder.redirect(Policy::none()).build()?))
+            .expect("default HTTP client");
+        ReqwestFetcher {
+            client,
+            single,
+            http,
+        }
+    }
+
+    pub fn from_config(http: &HttpConfig) -> Result<Self, AppError> {
+        Self::build(http, None)
+    }
+
+    fn build(http: &HttpConfig, proxy: Option<reqwest::Proxy>) -> Result<Self, AppError> {
+        let builder = |redirect: Policy| -> Result<reqwest::Client, AppError> {
+            let mut builder = client_builder(http)?.redirect(redirect);
+            if let Some(proxy) = &proxy {
+                builder = builder.proxy(proxy.clone());
+            }
+            Ok(builder.build()?)
+        };
+        Ok(ReqwestFetcher {
+            client: builder(Policy::default())?,
+            single: builder(Policy::none())?,
+            http: http.clone(),
+        })
+    }
+}
+
+impl Default for ReqwestFetcher {
+    fn default() -> Self {
+        Self::from_config(&HttpConfig::default()).expect("default HTTP client")
+    }
+}
+
+async fn read(response: reqwest::Response) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse {
+        status: response.status(),
+        url: response.url().clone(),
+        headers: response.headers().clone(),
+        body: response.text().await?,
+    })
+}
+
+#[async_trait]
+impl HttpFetcher for ReqwestFetcher {
+    async fn get(&self, url: &Url, headers: &HeaderMap) -> Result<HttpResponse, AppError> {
+        let response = self
+            .client
+            .get(url.clone())
+            .headers(headers.clone())
+            .send()
+            .await?;
+        read(response).await
+    }
+
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, AppError> {
+        let mut builder = self
+            .single
+            .request(request.method, request.url)
+            .headers(request.headers);
+        if let Some(form) = &request.form {
+            builder = builder.form(form);
+        }
+        read(builder.send().await?).await
+    }
+
+    fn via_proxy(&self, proxy: &Url) -> Result<Arc<dyn HttpFetcher>, AppError> {
+        let proxy = reqwest::Proxy::all(proxy.as_str())?;
+        Ok(Arc::new(Self::build(&self.http, Some(proxy))?))
+    }
+}
+
+// Answers requests with canned responses and records every URL requested.
+// The first fixture matching a URL answers it; unmatched URLs get a 404.
+// Fetchers "through a proxy" share the fixtures and the record.
+#[derive(Default, Clone)]
+pub struct FixtureFetcher {
+    fixtures: Arc<Mutex<Vec<Fixture>>>,
+    requests: Arc<Mutex<Vec<Url>>>,
+}
+
+struct Fixture {
+    matches: Box<dyn Fn(&Url) -> bool + Send + Sync>,
+    status: StatusCode,
+    body: String,
+}
+
+impl FixtureFetcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Answers the URLs `matches` accepts with `status` and `body`.
+    pub fn respond(
+        self,
+        matches: impl Fn(&Url) -> bool + Send + Sync + 'static,
+        status: StatusCode,
+        body: impl Into<String>,
+    ) -> Self {
+        self.fixtures.lock().unwrap().push(Fixture {
+            matches: Box::new(matches),
+            status,
+            body: body.into(),
+        });
+        self
+    }
+
+    // Answers every request to `path`, whatever its query, with a 200 and `body`.
+    pub fn respond_path(self, path: &str, body: impl Into<String>) -> Self {
+        let path = path.to_string();
+        self.respond(move |url| url.path() == path, StatusCode::OK, body)
+    }
+
+    // URLs requested so far, in order.
+    pub fn requests(&self) -> Vec<Url> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn answer(&self, url: &Url) -> HttpResponse {
+        self.requests.lock().unwrap().push(url.clone());
+        let (status, body) = self
+            .fixtures
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|fixture| (fixture.matches)(url))
+            .map_or((StatusCode::NOT_FOUND, String::new()), |fixture| {
+                (fixture.status, fixture.body.clone())
+            });
+        HttpResponse {
+            status,
+            url: url.clone(),
+            headers: HeaderMap::new(),
+            body,
+        }
+    }
+}
+
+impl fmt::Debug for FixtureFetcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FixtureFetcher")
+            .field("fixtures", &self.fixtures.lock().unwrap().len())
+            .field("requests", &self.requests.lock().unwrap().len())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl HttpFetcher for FixtureFetcher {
+    async fn get(&self, url: &Url, _headers: &HeaderMap) -> Result<HttpResponse, AppError> {
+        Ok(self.answer(url))
+    }
+
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, AppError> {
+        Ok(self.answer(&request.url))
+    }
+
+    fn via_proxy(&self, _proxy: &Url) -> Result<Arc<dyn HttpFetcher>, AppError> {
+        Ok(Arc::new(self.clone()))
+    }
+}
@@ -8,6 +8,7 @@ use crate::models::place::Place;
 use serde_json::{to_string_pretty, Value};
 use std::fs;
 use std::io::Write;
+use std::path::Path;
 
 pub fn save_json_to_file(json: &Value, filename: &str) -> Result<(), AppError> {
     let formatted_json = to_string_pretty(json)?;
@@ -17,12 +18,17 @@ pub fn save_json_to_file(json: &Value, filename: &str) -> Result<(), AppError> {
     Ok(())
 }
 
-pub fn save_places(places: &[Place]) -> Result<(), AppError> {
-    let json_output = to_string_pretty(&*places)?;
-    std::fs::write("all_places_output.json", json_output)?;
+pub fn save_places(places: &[Place], path: &Path) -> Result<(), AppError> {
+    let json_output = to_string_pretty(places)?;
+    std::fs::write(path, json_output)?;
     Ok(())
 }
 
+pub fn load_places(path: &Path) -> Result<Vec<Place>, AppError> {
+    let raw = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&raw)?)
+}
+
 // Extracts a nested value from a JSON structure using a list of indexes.
 pub fn get_nested_value<'a>(data: &'a Value, indexes: &[usize]) -> Option<&'a Value> {
     let mut current = data;
@@ -37,8 +43,6 @@ pub fn prepare(input: &str) -> Result<Value, AppError> {
     let prepared = input.replace("/*\"\"*/", "");
     let json: Value = serde_json::from_str(&prepared)?;
 
-    save_json_to_file(&json, "raw_response.json")?;
-
     if let Some(d_str) = json.get("d").and_then(|v| v.as_str()) {
         let cleaned_d = d_str.trim_start_matches(")]}'\n");
         let d_json: Value = serde_json::from_str(cleaned_d)?;
@@ -46,8 +50,6 @@ pub fn prepare(input: &str) -> Result<Value, AppError> {
         let mut modified_json = json.clone();
         modified_json["d"] = d_json;
 
-        save_json_to_file(&modified_json, "cleaned_response.json")?;
-
         if let Some(first_key_value) = modified_json
             .get("d")
             .and_then(|v| v.get(0))
@@ -75,10 +77,10 @@ pub fn build_results(json: &Value, searched_coords: Coordinates) -> Result<Vec<P
     if let Some(arr) = json.as_array() {
         let results: Vec<Place> = arr
             .iter()
-            .filter_map(|place| {
+            .map(|place| {
                 let lookup = |indexes: &[usize]| get_nested_value(place, indexes);
 
-                Some(Place {
+                Place {
                     address: build_address(place),
                     name: extract_value(lookup(&[11])),
                     tags: extract_value(lookup(&[13])),
@@ -94,7 +96,8 @@ pub fn build_results(json: &Value, searched_coords: Coordinates) -> Result<Vec<P
                     online_delivery_link: extract_value(lookup(&[75, 0, 0, 2, 0, 1, 2, 0])),
                     coordinates: build_coordinates(place),
                     searched_coords: searched_coords.clone(),
-                })
+                    page_offset: None,
+                }
             })
             .collect();
 
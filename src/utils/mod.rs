@@ -1,3 +1,9 @@
+pub mod circuit_breaker;
+pub mod export;
 pub mod http_client;
+pub mod http_fetcher;
 pub mod json_utils;
 pub mod logger;
+pub mod proxy_pool;
+pub mod rate_limiter;
+pub mod retry;
@@ -0,0 +1,53 @@
+use std::sync::{Arc, Mutex};
+use tokio::time::{sleep, Duration, Instant};
+
+// Token bucket shared by every request of a scrape: it holds up to `burst`
+// tokens, refills at `requests_per_second` and each request takes one.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+    requests_per_second: f64,
+    burst: f64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    // Starts with a full bucket, so the first `burst` requests go out at once.
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        RateLimiter {
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: burst,
+                refilled_at: Instant::now(),
+            })),
+            requests_per_second,
+            burst,
+        }
+    }
+
+    // Waits until a token is available and takes it.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+                let now = Instant::now();
+                let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+                bucket.tokens =
+                    (bucket.tokens + elapsed * self.requests_per_second).min(self.burst);
+                bucket.refilled_at = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.requests_per_second)
+            };
+            sleep(wait).await;
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::config::RetryPolicy;
+use crate::error::AppError;
+
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::future::Future;
+use std::time::SystemTime;
+use tokio::time::{sleep, Duration};
+
+impl RetryPolicy {
+    // Exponential backoff before retry number `retry` (from 1), capped at
+    // `max_backoff_ms` and reduced by up to `jitter` of itself at random.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponential = self.initial_backoff_ms as f64 * self.multiplier.powi(retry as i32 - 1);
+        let capped = exponential.min(self.max_backoff_ms as f64);
+        let jitter = rand::thread_rng().gen_range(0.0..=self.jitter.clamp(0.0, 1.0));
+        Duration::from_millis((capped * (1.0 - jitter)) as u64)
+    }
+}
+
+// Wait requested by the `Retry-After` header of a response.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_retry_after)
+}
+
+// `Retry-After` is either a number of seconds or an HTTP date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+// Runs `attempt` until it succeeds, fails permanently or `max_attempts` is
+// reached, and returns its last result with the number of retries made.
+pub async fn with_retries<T, F, Fut>(
+    policy: &RetryPolicy,
+    what: &str,
+    mut attempt: F,
+) -> (Result<T, AppError>, u32)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, AppError>>,
+{
+    let mut retries = 0;
+    loop {
+        match attempt().await {
+            // A server asking for a longer wait than the policy allows is not
+            // retried early; the request fails instead.
+            Err(e) if e.retry_after() > Some(Duration::from_millis(policy.max_backoff_ms)) => {
+                log::warn!(
+                    "{} failed ({}) and the server asked to wait {:?}, longer than the {} ms the retry policy allows",
+                    what,
+                    e,
+                    e.retry_after().unwrap_or_default(),
+                    policy.max_backoff_ms
+                );
+                return (Err(e), retries);
+            }
+            Err(e) if e.is_retryable() && retries + 1 < policy.max_attempts => {
+                retries += 1;
+                let delay = e.retry_after().unwrap_or_else(|| policy.backoff(retries));
+                log::warn!(
+                    "{} failed on attempt {}/{} ({}), retrying in {:?}",
+                    what,
+                    retries,
+                    policy.max_attempts,
+                    e,
+                    delay
+                );
+                sleep(delay).await;
+            }
+            Err(e) => {
+                if retries > 0 {
+                    log::warn!("{} failed after {} attempts: {}", what, retries + 1, e);
+                }
+                return (Err(e), retries);
+            }
+            Ok(value) => {
+                if retries > 0 {
+                    log::info!("{} succeeded on attempt {}", what, retries + 1);
+                }
+                return (Ok(value), retries);
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use google_maps::config::{BrowserConfig, BrowserProfile, Job, Locale};
+use google_maps::error::AppError;
+use google_maps::models::Cell;
+use google_maps::services::search_backend::{ScrapingBackend, SearchBackend};
+use google_maps::services::session::SessionPool;
+use google_maps::utils::http_client::{accept_language, builtin_profile, profile_headers};
+use google_maps::utils::http_fetcher::{FixtureFetcher, ReqwestFetcher};
+use google_maps::utils::proxy_pool::ProxyLease;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn locale(language: &str, region: Option<&str>) -> Locale {
+    Locale {
+        language: language.to_string(),
+        region: region.map(str::to_string),
+    }
+}
+
+fn custom(name: &str, header: &str, value: &str) -> BrowserProfile {
+    BrowserProfile {
+        name: name.to_string(),
+        user_agent: format!("{}/1.0", name),
+        accept_language: None,
+        headers: BTreeMap::from([(header.to_string(), value.to_string())]),
+    }
+}
+
+#[test]
+fn builtin_profiles_send_consistent_client_hints() {
+    let locale = Locale::default();
+    let chrome = profile_headers(&builtin_profile("chrome").unwrap(), &locale).unwrap();
+    let firefox = profile_headers(&builtin_profile("firefox").unwrap(), &locale).unwrap();
+    let mobile = profile_headers(&builtin_profile("mobile").unwrap(), &locale).unwrap();
+
+    assert!(chrome["user-agent"]
+        .to_str()
+        .unwrap()
+        .contains("Chrome/141"));
+    assert!(chrome["sec-ch-ua"].to_str().unwrap().contains("\"141\""));
+    assert!(firefox["user-agent"].to_str().unwrap().contains("Firefox"));
+    assert!(!firefox.contains_key("sec-ch-ua"));
+    assert_eq!(mobile["sec-ch-ua-mobile"], "?1");
+    assert_eq!(mobile["sec-ch-ua-platform"], "\"Android\"");
+    assert!(builtin_profile("netscape").is_none());
+}
+
+#[test]
+fn accept_language_follows_the_locale() {
+    assert_eq!(accept_language(&locale("en", None)), "en");
+    assert_eq!(accept_language(&locale("en", Some("pk"))), "en-PK,en;q=0.9");
+    assert_eq!(
+        accept_language(&locale("ur", Some("pk"))),
+        "ur-PK,ur;q=0.9,en;q=0.8"
+    );
+    assert_eq!(
+        accept_language(&locale("zh-CN", None)),
+        "zh-CN,zh;q=0.9,en;q=0.8"
+    );
+}
+
+#[test]
+fn bad_header_values_are_errors() {
+    let profile = custom("broken", "sec-ch-ua", "line\nbreak");
+    assert!(matches!(
+        profile_headers(&profile, &Locale::default()),
+        Err(AppError::InvalidHeader { ref header, .. }) if header == "sec-ch-ua"
+    ));
+
+    let job = Job {
+        browser: BrowserConfig {
+            profiles: vec!["broken".to_string()],
+            custom: vec![profile],
+            ..BrowserConfig::default()
+        },
+        ..Job::default()
+    };
+    assert!(matches!(job.validate(), Err(AppError::InvalidJob { .. })));
+
+    let unknown = BrowserConfig {
+        profiles: vec!["netscape".to_string()],
+        ..BrowserConfig::default()
+    };
+    assert!(matches!(
+        SessionPool::new(&unknown, &Locale::default()),
+        Err(AppError::UnknownProfile(_))
+    ));
+}
+
+#[tokio::test]
+async fn sessions_rotate_profiles_and_tokens() {
+    let config = BrowserConfig {
+        profiles: vec!["first".to_string(), "second".to_string()],
+        custom: vec![
+            custom("first", "x-profile", "1"),
+            custom("second", "x-profile", "2"),
+        ],
+        session_requests: 2,
+        bootstrap: false,
+        ..BrowserConfig::default()
+    };
+    let pool = SessionPool::new(&config, &Locale::default()).unwrap();
+    let direct = ProxyLease::direct(Arc::new(FixtureFetcher::new()));
+
+    let mut sessions = Vec::new();
+    for _ in 0..6 {
+        sessions.push(pool.session(&direct).await);
+    }
+    for pair in sessions.chunks(2) {
+        assert!(Arc::ptr_eq(&pair[0], &pair[1]));
+    }
+    for pair in sessions.windows(2).skip(1).step_by(2) {
+        assert_ne!(pair[0].profile, pair[1].profile);
+        assert_ne!(pair[0].tokens, pair[1].tokens);
+    }
+    assert_eq!(
+        sessions[0].headers["user-agent"],
+        format!("{}/1.0", sessions[0].profile)
+    );
+}
+
+#[tokio::test]
+async fn scraping_backend_sends_the_session_headers() {
+    let server = MockServer::start().await;
+    Mock::given(path("/search"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(")]}'\n[]"))
+        .mount(&server)
+        .await;
+
+    let config = BrowserConfig {
+        profiles: vec!["firefox".to_string()],
+        bootstrap: false,
+        ..BrowserConfig::default()
+    };
+    let locale = locale("ur", Some("pk"));
+    let sessions = SessionPool::new(&config, &locale).unwrap();
+    let backend = ScrapingBackend::new(Arc::new(ReqwestFetcher::default()), locale, 0)
+        .unwrap()
+        .base_url(format!("{}/search", server.uri()))
+        .sessions(Arc::new(sessions));
+
+    let _ = backend
+        .search(&Cell::new(24.92, 67.05, 0.01, 0.01), "biryani", None)
+        .await;
+
+    let requests = server.received_requests().await.unwrap();
+    let headers = &requests[0].headers;
+    assert!(headers["user-agent"].to_str().unwrap().contains("Firefox"));
+    assert_eq!(headers["accept-language"], "ur-PK,ur;q=0.9,en;q=0.8");
+    assert!(!headers.contains_key("sec-ch-ua"));
+
+    let psi = requests[0]
+        .url
+        .query_pairs()
+        .find(|(name, _)| name == "psi")
+        .unwrap()
+        .1
+        .into_owned();
+    assert!(!psi.starts_with("1pk8Z7ObMvuRkdUPhKD9iAY"));
+}
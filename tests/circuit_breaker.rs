@@ -0,0 +1,178 @@
+mod common;
+
+use google_maps::config::{BreakerAction, CircuitBreakerConfig, Locale};
+use google_maps::error::AppError;
+use google_maps::models::Coordinates;
+use google_maps::services::google_maps::fetch_places;
+use google_maps::services::response_classifier::{classify, ResponseKind};
+use google_maps::services::search_backend::ScrapingBackend;
+use google_maps::utils::circuit_breaker::CircuitBreaker;
+use google_maps::utils::http_fetcher::ReqwestFetcher;
+use google_maps::ScrapeReport;
+
+use futures::StreamExt;
+use reqwest::header::HeaderMap;
+use std::sync::Arc;
+use url::Url;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const SORRY_PAGE: &str = "<html><body>Our systems have detected unusual traffic from your \
+     computer network.<div class=\"g-recaptcha\"></div></body></html>";
+
+fn url(raw: &str) -> Url {
+    Url::parse(raw).unwrap()
+}
+
+fn breaker_config(action: BreakerAction) -> CircuitBreakerConfig {
+    CircuitBreakerConfig {
+        window: 4,
+        min_requests: 2,
+        block_threshold: 0.5,
+        action,
+        pause_secs: 0,
+        max_pauses: 1,
+        ..CircuitBreakerConfig::default()
+    }
+}
+
+fn backend(server: &MockServer) -> ScrapingBackend {
+    ScrapingBackend::new(Arc::new(ReqwestFetcher::default()), Locale::default(), 0)
+        .unwrap()
+        .base_url(format!("{}/search", server.uri()))
+}
+
+async fn scrape(server: &MockServer, action: BreakerAction) -> ScrapeReport {
+    let builder = common::scraper(Arc::new(backend(server)))
+        .grid(common::grid(4, 4))
+        .circuit_breaker(breaker_config(action));
+    common::scrape(builder).await
+}
+
+#[test]
+fn classifies_data_and_block_pages() {
+    let search = url("https://www.google.com/search?tbm=map");
+    assert_eq!(
+        classify(&search, ")]}'\n[[\"biryani\"]]"),
+        ResponseKind::Data
+    );
+    assert_eq!(classify(&search, SORRY_PAGE), ResponseKind::Captcha);
+    assert_eq!(
+        classify(&url("https://www.google.com/sorry/index?continue=x"), ""),
+        ResponseKind::Captcha
+    );
+    assert_eq!(
+        classify(
+            &url("https://consent.google.com/ml?continue=x"),
+            "<html></html>"
+        ),
+        ResponseKind::ConsentPage
+    );
+    assert_eq!(
+        classify(
+            &search,
+            "<form action=\"https://consent.google.com/save\"></form>"
+        ),
+        ResponseKind::ConsentPage
+    );
+    assert_eq!(
+        classify(&search, "<!DOCTYPE html><html><title>Error</title></html>"),
+        ResponseKind::UnknownHtml
+    );
+}
+
+#[tokio::test]
+async fn redirect_to_sorry_page_is_a_captcha_error() {
+    let server = MockServer::start().await;
+    Mock::given(path("/search"))
+        .respond_with(
+            ResponseTemplate::new(302)
+                .insert_header("Location", format!("{}/sorry/index", server.uri())),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(path("/sorry/index"))
+        .respond_with(ResponseTemplate::new(429).set_body_string(SORRY_PAGE))
+        .mount(&server)
+        .await;
+
+    let url = format!("{}/search?q=biryani", server.uri());
+    let searched = Coordinates {
+        lat: Some(24.92),
+        long: Some(67.05),
+    };
+    let error = fetch_places(
+        &ReqwestFetcher::default(),
+        &url,
+        &HeaderMap::new(),
+        searched,
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, AppError::CaptchaPage(ref u) if u.contains("/sorry/index")));
+    assert!(error.is_block());
+    assert!(!error.is_retryable());
+}
+
+#[tokio::test]
+async fn abort_stops_the_run_once_blocks_cross_the_threshold() {
+    let server = MockServer::start().await;
+    Mock::given(path("/search"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(SORRY_PAGE))
+        .mount(&server)
+        .await;
+
+    let report = scrape(&server, BreakerAction::Abort).await;
+
+    assert_eq!(report.requests, 2);
+    assert_eq!(report.blocked_requests, 2);
+    assert!(report.aborted.is_some());
+    assert!(report.places.is_empty());
+}
+
+#[tokio::test]
+async fn aborted_streams_end_with_the_reason() {
+    let server = MockServer::start().await;
+    Mock::given(path("/search"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(SORRY_PAGE))
+        .mount(&server)
+        .await;
+
+    let scraper = common::scraper(Arc::new(backend(&server)))
+        .grid(common::grid(4, 4))
+        .circuit_breaker(breaker_config(BreakerAction::Abort))
+        .build()
+        .unwrap();
+    let items: Vec<_> = scraper.stream().await.unwrap().collect().await;
+
+    assert!(matches!(items.as_slice(), [Err(AppError::CircuitOpen(_))]));
+}
+
+#[tokio::test]
+async fn pause_resumes_until_the_pauses_run_out() {
+    let server = MockServer::start().await;
+    Mock::given(path("/search"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            "<html><body>Before you continue to Google\
+                 <form action=\"https://consent.google.com/save\"></form></body></html>",
+        ))
+        .mount(&server)
+        .await;
+
+    let report = scrape(&server, BreakerAction::Pause).await;
+
+    // Two blocked requests open the circuit, one pause allows two more
+    assert_eq!(report.requests, 4);
+    assert_eq!(report.blocked_requests, 4);
+    assert!(report.aborted.is_some());
+}
+
+#[tokio::test]
+async fn breaker_ignores_occasional_blocks() {
+    let breaker = CircuitBreaker::new(breaker_config(BreakerAction::Abort));
+    for blocked in [false, false, false, true, false, false, false, true] {
+        breaker.record(blocked);
+    }
+    assert!(breaker.tripped().is_none());
+    assert!(breaker.wait().await.is_ok());
+}
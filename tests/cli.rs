@@ -0,0 +1,101 @@
+use google_maps::cli::{Cli, Command};
+use google_maps::config::GeocoderConfig;
+use google_maps::services::grid_generator::Tiling;
+
+use clap::error::ErrorKind;
+use clap::Parser;
+
+fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+    Cli::try_parse_from(["google_maps"].iter().chain(args))
+}
+
+fn error(args: &[&str]) -> ErrorKind {
+    parse(args).unwrap_err().kind()
+}
+
+#[test]
+fn bbox_is_parsed_in_south_west_north_east_order() {
+    let cli = parse(&["geocode", "--bbox", "-33.9,18.4,-33.8,18.5"]).unwrap();
+    let Command::Geocode(args) = cli.command else {
+        panic!("expected the geocode command");
+    };
+    assert!(matches!(
+        args.geocoder(),
+        GeocoderConfig::Bbox { south, west, north, east }
+            if (south, west, north, east) == (-33.9, 18.4, -33.8, 18.5)
+    ));
+
+    for bbox in [
+        "24.9,67.0,24.95",
+        "24.9,67.0,north,67.1",
+        "24.95,67.0,24.9,67.1",
+        "24.9,67.1,24.95,67.0",
+    ] {
+        assert_eq!(
+            error(&["geocode", "--bbox", bbox]),
+            ErrorKind::ValueValidation,
+            "{}",
+            bbox
+        );
+    }
+}
+
+#[test]
+fn cell_size_conflicts_with_rows_and_cols() {
+    assert_eq!(
+        error(&["grid", "--cell-size", "500", "--rows", "3"]),
+        ErrorKind::ArgumentConflict
+    );
+    assert_eq!(
+        error(&["grid", "--cell-size", "500", "--cols", "3"]),
+        ErrorKind::ArgumentConflict
+    );
+    let cli = parse(&["grid", "--cell-size", "500"]).unwrap();
+    let Command::Grid(args) = cli.command else {
+        panic!("expected the grid command");
+    };
+    assert_eq!(args.grid.spec().cell_size_m, Some(500.0));
+}
+
+#[test]
+fn hex_and_circle_tilings_and_stats_require_a_radius() {
+    for tiling in ["hex", "circle"] {
+        assert_eq!(
+            error(&["grid", "--tiling", tiling]),
+            ErrorKind::MissingRequiredArgument
+        );
+    }
+    assert_eq!(
+        error(&["grid", "--stats"]),
+        ErrorKind::MissingRequiredArgument
+    );
+
+    let cli = parse(&["grid", "--tiling", "hex", "--radius", "500", "--stats"]).unwrap();
+    let Command::Grid(args) = cli.command else {
+        panic!("expected the grid command");
+    };
+    assert!(args.stats);
+    assert_eq!(args.grid.spec().tiling, Tiling::Hex);
+    assert_eq!(args.grid.spec().search_radius_m, Some(500.0));
+    assert!(parse(&["grid"]).is_ok());
+}
+
+#[test]
+fn adaptive_and_clipping_flags_require_their_mode() {
+    assert_eq!(
+        error(&["scrape", "-q", "biryani", "--max-depth", "2"]),
+        ErrorKind::MissingRequiredArgument
+    );
+    assert_eq!(
+        error(&["scrape", "-q", "biryani", "--min-cell-size", "100"]),
+        ErrorKind::MissingRequiredArgument
+    );
+    assert_eq!(
+        error(&["scrape", "-q", "biryani", "--clip-places"]),
+        ErrorKind::MissingRequiredArgument
+    );
+    assert_eq!(error(&["scrape"]), ErrorKind::MissingRequiredArgument);
+
+    assert!(parse(&["scrape", "-q", "biryani", "--adaptive", "--max-depth", "2"]).is_ok());
+    assert!(parse(&["scrape", "-q", "biryani", "-b", "area.wkt", "--clip-places"]).is_ok());
+}
@@ -0,0 +1,85 @@
+// Helpers shared by the integration tests, each of which uses only some of them.
+#![allow(dead_code)]
+
+use google_maps::config::{GridSpec, RateLimit};
+use google_maps::models::{Place, Viewport};
+use google_maps::services::search_backend::SearchBackend;
+use google_maps::{ScrapeReport, Scraper, ScraperBuilder};
+
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+// Part of Karachi, about 10 by 5.6 km.
+pub fn viewport() -> Viewport {
+    Viewport {
+        northeast: (24.95, 67.10),
+        southwest: (24.90, 67.00),
+    }
+}
+
+pub fn grid(rows: usize, cols: usize) -> GridSpec {
+    GridSpec {
+        rows,
+        cols,
+        ..GridSpec::default()
+    }
+}
+
+// Requests as fast as `concurrency` cells at a time allow.
+pub fn rate_limit(concurrency: usize) -> RateLimit {
+    RateLimit {
+        concurrency,
+        requests_per_second: 1000.0,
+        burst: 10,
+    }
+}
+
+// Searches the viewport for "biryani" through `backend`, one cell at a time
+// on a single-cell grid unless the test sets otherwise.
+pub fn scraper(backend: Arc<dyn SearchBackend>) -> ScraperBuilder {
+    Scraper::builder()
+        .viewport(viewport())
+        .query("biryani")
+        .grid(grid(1, 1))
+        .rate_limit(rate_limit(1))
+        .backend(backend)
+}
+
+pub async fn scrape(builder: ScraperBuilder) -> ScrapeReport {
+    builder.build().unwrap().run().await.unwrap()
+}
+
+// A place as the Maps search returns it, with only the fields that are read set.
+pub fn place(name: &str, place_id: &str, lat: f64, lng: f64) -> Value {
+    let mut place = vec![Value::Null; 79];
+    place[9] = json!([null, null, lng, lat]);
+    place[11] = json!(name);
+    place[18] = json!(format!("{}, Karachi", name));
+    place[78] = json!(place_id);
+    Value::Array(place)
+}
+
+// Body of a Maps search response listing `places`.
+pub fn maps_page(places: &[Value]) -> String {
+    let items: Vec<Value> = places
+        .iter()
+        .map(|place| {
+            let mut item = vec![Value::Null; 15];
+            item[14] = place.clone();
+            Value::Array(item)
+        })
+        .collect();
+    let d = format!(")]}}'\n{}", json!([[null, items]]));
+    format!("{}/*\"\"*/", json!({ "c": 0, "d": d }))
+}
+
+// A parsed place holding nothing but its id, as stub backends return.
+pub fn place_with_id(place_id: &str) -> Place {
+    serde_json::from_value(json!({
+        "address": {},
+        "coordinates": {},
+        "searched_coords": {},
+        "place_id": place_id
+    }))
+    .unwrap()
+}
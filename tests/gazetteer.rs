@@ -0,0 +1,67 @@
+use google_maps::error::AppError;
+use google_maps::services::gazetteer::Gazetteer;
+
+use std::fs;
+
+#[test]
+fn resolves_cities_and_neighbourhoods_from_the_bundled_csv() {
+    let gazetteer = Gazetteer::bundled();
+    let karachi = gazetteer.resolve("karachi").unwrap();
+    assert_eq!(karachi.country_code, "PK");
+
+    let north_nazimabad = gazetteer.resolve("North Nazimabad, Karachi").unwrap();
+    assert_eq!(north_nazimabad.viewport.southwest, (24.92, 67.01));
+    assert!(matches!(
+        gazetteer.resolve("North Nazimabad, Lahore"),
+        Err(AppError::AreaNotFound(_))
+    ));
+}
+
+#[test]
+fn ambiguous_names_need_a_country_code() {
+    let gazetteer = Gazetteer::bundled();
+    assert!(matches!(
+        gazetteer.resolve("Hyderabad"),
+        Err(AppError::AmbiguousArea { countries, .. }) if countries == "IN, PK"
+    ));
+    assert_eq!(
+        gazetteer.resolve("Hyderabad, IN").unwrap().country_code,
+        "IN"
+    );
+
+    let preferring_pk = Gazetteer::bundled().country(Some("PK".to_string()));
+    assert_eq!(
+        preferring_pk.resolve("Hyderabad").unwrap().country_code,
+        "PK"
+    );
+}
+
+#[test]
+fn geonames_dumps_are_sized_from_population_and_nested_by_position() {
+    let dump = [
+        "1174872\tKarachi\tKarachi\tKarachee,کراچی\t24.8608\t67.0104\tP\tPPLA\tPK\t\t05\t\t\t\t11624219\t\t8\tAsia/Karachi\t2019-12-06",
+        "1168966\tNorth Nazimabad\tNorth Nazimabad\t\t24.9392\t67.0372\tP\tPPLX\tPK\t\t05\t\t\t\t0\t\t25\tAsia/Karachi\t2019-12-06",
+    ]
+    .join("\n");
+    let path = std::env::temp_dir().join(format!("geonames-{}.txt", std::process::id()));
+    fs::write(&path, dump).unwrap();
+    let gazetteer = Gazetteer::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let karachi = gazetteer.resolve("کراچی").unwrap();
+    let (south, _) = karachi.viewport.southwest;
+    let (north, _) = karachi.viewport.northeast;
+    assert!((north - south) > 0.4 && (north - south) < 0.6);
+
+    let section = gazetteer.resolve("North Nazimabad, Karachi").unwrap();
+    assert_eq!(section.population, 0);
+}
+
+#[test]
+fn rejects_csv_without_bounding_box_columns() {
+    let path = std::env::temp_dir().join(format!("gazetteer-{}.csv", std::process::id()));
+    fs::write(&path, "name,country_code\nKarachi,PK\n").unwrap();
+    let result = Gazetteer::load(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(matches!(result, Err(AppError::InvalidGazetteer(_))));
+}
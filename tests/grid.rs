@@ -0,0 +1,224 @@
+use google_maps::config::{AdaptiveConfig, GridSpec, Job};
+use google_maps::error::AppError;
+use google_maps::models::{Cell, Viewport};
+use google_maps::services::grid_generator::{
+    compare_tilings, generate_cells, generate_circle_grid, generate_grid_cells, generate_hex_grid,
+    generate_metric_grid, tiling_stats, Tiling,
+};
+use google_maps::services::quadtree::should_split;
+
+const EPSILON: f64 = 1e-9;
+
+fn karachi() -> Viewport {
+    Viewport {
+        northeast: (24.95, 67.10),
+        southwest: (24.90, 67.00),
+    }
+}
+
+#[test]
+fn cells_tile_the_viewport_exactly() {
+    let viewport = karachi();
+    let cells = generate_cells(&viewport, 2, 4);
+    assert_eq!(cells.len(), 8);
+
+    let (south, west, north, east) = cells.iter().map(Cell::bounds).fold(
+        (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+        |(s, w, n, e), (south, west, north, east)| {
+            (s.min(south), w.min(west), n.max(north), e.max(east))
+        },
+    );
+    assert!((south - viewport.southwest.0).abs() < EPSILON);
+    assert!((west - viewport.southwest.1).abs() < EPSILON);
+    assert!((north - viewport.northeast.0).abs() < EPSILON);
+    assert!((east - viewport.northeast.1).abs() < EPSILON);
+
+    // Cells do not overlap, so their areas add up to the area of the viewport
+    let area: f64 = cells.iter().map(|cell| cell.lat_span * cell.lng_span).sum();
+    assert!((area - 0.05 * 0.10).abs() < EPSILON);
+    assert!((cells[0].lat - 24.9125).abs() < EPSILON);
+    assert!((cells[0].lng - 67.0125).abs() < EPSILON);
+}
+
+#[test]
+fn split_cells_into_quadrants_covering_the_cell() {
+    let cell = Cell::new(24.92, 67.05, 0.02, 0.04);
+    let quadrants = cell.split();
+
+    for quadrant in &quadrants {
+        assert_eq!(quadrant.depth, 1);
+        assert!((quadrant.lat_span - 0.01).abs() < EPSILON);
+        assert!((quadrant.lng_span - 0.02).abs() < EPSILON);
+    }
+    let (south, west, _, _) = quadrants[0].bounds();
+    let (_, _, north, east) = quadrants[3].bounds();
+    let bounds = cell.bounds();
+    assert!((south - bounds.0).abs() < EPSILON && (west - bounds.1).abs() < EPSILON);
+    assert!((north - bounds.2).abs() < EPSILON && (east - bounds.3).abs() < EPSILON);
+    assert_eq!(quadrants[3].split()[0].depth, 2);
+}
+
+#[test]
+fn cells_split_until_the_depth_or_size_limit() {
+    let config = AdaptiveConfig {
+        max_depth: 2,
+        min_cell_size_m: 250.0,
+        new_places_threshold: 15,
+    };
+    // About 1.1 km high, so its quadrants are above the minimum size
+    let cell = Cell::new(24.92, 67.05, 0.01, 0.01);
+
+    assert!(should_split(&cell, true, 0, &config));
+    assert!(should_split(&cell, false, 15, &config));
+    assert!(!should_split(&cell, false, 14, &config));
+
+    let deep = Cell { depth: 2, ..cell };
+    assert!(!should_split(&deep, true, 20, &config));
+    let small = Cell::new(24.92, 67.05, 0.004, 0.004);
+    assert!(!should_split(&small, true, 20, &config));
+}
+
+#[test]
+fn metric_cells_cover_the_viewport_at_about_the_requested_size() {
+    let viewport = karachi();
+    let cells = generate_metric_grid(&viewport, 1000.0);
+
+    // 0.05 degrees of latitude is about 5.6 km, so 6 rows of cells
+    let rows: Vec<f64> = cells
+        .iter()
+        .map(|cell| cell.lat)
+        .fold(Vec::new(), |mut rows, lat| {
+            if !rows.iter().any(|row| (row - lat).abs() < EPSILON) {
+                rows.push(lat);
+            }
+            rows
+        });
+    assert_eq!(rows.len(), 6);
+    for cell in &cells {
+        let (height, width) = cell.size_m();
+        assert!(height <= 1000.0 && height > 900.0, "{}", height);
+        assert!(width <= 1000.0 && width > 900.0, "{}", width);
+    }
+    let area: f64 = cells.iter().map(|cell| cell.lat_span * cell.lng_span).sum();
+    assert!((area - 0.05 * 0.10).abs() < EPSILON);
+}
+
+#[test]
+fn metric_cells_widen_in_longitude_away_from_the_equator() {
+    let equator = Viewport {
+        northeast: (0.05, 1.0),
+        southwest: (-0.05, 0.0),
+    };
+    let north = Viewport {
+        northeast: (60.05, 1.0),
+        southwest: (59.95, 0.0),
+    };
+    let equator_cells = generate_metric_grid(&equator, 1000.0);
+    let north_cells = generate_metric_grid(&north, 1000.0);
+
+    // A degree of longitude at 60° is half as long, so rows hold half the cells
+    assert_eq!(equator_cells.len(), 12 * 112);
+    assert_eq!(north_cells.len(), 12 * 56);
+    let ratio = north_cells[0].lng_span / equator_cells[0].lng_span;
+    assert!((ratio - 2.0).abs() < 0.01, "{}", ratio);
+    let (height, width) = north_cells[0].size_m();
+    assert!((height - width).abs() < 100.0);
+}
+
+#[test]
+fn grid_specs_without_cells_are_rejected() {
+    let invalid = [
+        GridSpec {
+            rows: 0,
+            ..GridSpec::default()
+        },
+        GridSpec {
+            cols: 0,
+            ..GridSpec::default()
+        },
+        GridSpec {
+            cell_size_m: Some(0.0),
+            ..GridSpec::default()
+        },
+        GridSpec {
+            cell_size_m: Some(f64::INFINITY),
+            ..GridSpec::default()
+        },
+    ];
+    for spec in &invalid {
+        assert!(matches!(spec.validate(), Err(AppError::InvalidGrid(_))));
+        let job = Job {
+            grid: spec.clone(),
+            ..Job::default()
+        };
+        assert!(matches!(job.validate(), Err(AppError::InvalidJob { .. })));
+    }
+    assert!(GridSpec::default().validate().is_ok());
+}
+
+#[test]
+fn hex_and_circle_tilings_leave_no_gap() {
+    let viewport = karachi();
+    let hex = generate_hex_grid(&viewport, 500.0);
+    let circle = generate_circle_grid(&viewport, 500.0);
+
+    let hex_stats = tiling_stats(Tiling::Hex, &viewport, &hex, 500.0);
+    let circle_stats = tiling_stats(Tiling::Circle, &viewport, &circle, 500.0);
+    assert_eq!(hex_stats.coverage, 1.0);
+    assert_eq!(circle_stats.coverage, 1.0);
+    // The triangular lattice needs fewer points and overlaps less
+    assert!(hex.len() < circle.len());
+    assert!(hex_stats.overlap < circle_stats.overlap);
+
+    let spec = GridSpec {
+        tiling: Tiling::Hex,
+        search_radius_m: Some(500.0),
+        ..GridSpec::default()
+    };
+    let cells = generate_grid_cells(&viewport, &spec);
+    assert_eq!(cells.len(), hex.len());
+    let (height, width) = cells[0].size_m();
+    assert!((height - 1000.0).abs() < 1e-6 && (width - 1000.0).abs() < 1e-6);
+}
+
+#[test]
+fn stats_compare_every_tiling() {
+    let viewport = karachi();
+    let spec = GridSpec {
+        rows: 3,
+        cols: 3,
+        ..GridSpec::default()
+    };
+    let stats = compare_tilings(&viewport, &spec, 500.0).unwrap();
+
+    let tilings: Vec<Tiling> = stats.iter().map(|stats| stats.tiling).collect();
+    assert_eq!(tilings, [Tiling::Grid, Tiling::Hex, Tiling::Circle]);
+    // Nine points 500 m wide cannot reach the whole 10 x 5.6 km viewport
+    assert_eq!(stats[0].points, 9);
+    assert!(stats[0].coverage < 0.5);
+    assert_eq!(stats[1].coverage, 1.0);
+    assert_eq!(stats[2].coverage, 1.0);
+    assert!(stats.iter().all(|stats| stats.overlap >= 0.0));
+}
+
+#[test]
+fn search_radius_must_be_positive_and_finite() {
+    let viewport = karachi();
+    for radius in [0.0, -10.0, f64::NAN, f64::INFINITY] {
+        assert!(matches!(
+            compare_tilings(&viewport, &GridSpec::default(), radius),
+            Err(AppError::InvalidGrid(_))
+        ));
+        let spec = GridSpec {
+            tiling: Tiling::Hex,
+            search_radius_m: Some(radius),
+            ..GridSpec::default()
+        };
+        assert!(matches!(spec.validate(), Err(AppError::InvalidGrid(_))));
+    }
+    let spec = GridSpec {
+        tiling: Tiling::Circle,
+        ..GridSpec::default()
+    };
+    assert!(matches!(spec.validate(), Err(AppError::InvalidGrid(_))));
+}
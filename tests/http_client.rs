@@ -0,0 +1,141 @@
+use google_maps::config::{GridSpec, HttpConfig, Job, PlacesEndpoint, RateLimit, RetryPolicy};
+use google_maps::error::AppError;
+use google_maps::models::Viewport;
+use google_maps::services::places_api::PlacesBackend;
+use google_maps::utils::http_client::{build_client, load_ca_bundle};
+use google_maps::utils::http_fetcher::ReqwestFetcher;
+use google_maps::Scraper;
+
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn http(timeout_secs: u64) -> HttpConfig {
+    HttpConfig {
+        timeout_secs,
+        read_timeout_secs: timeout_secs,
+        ..HttpConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn hung_requests_time_out_instead_of_blocking_the_run() {
+    let server = MockServer::start().await;
+    Mock::given(path("/textsearch/json"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(30)))
+        .mount(&server)
+        .await;
+
+    let client = build_client(&http(1)).unwrap();
+    let error = client
+        .get(format!("{}/textsearch/json", server.uri()))
+        .send()
+        .await
+        .unwrap_err();
+    assert!(error.is_timeout());
+
+    let backend = PlacesBackend::new(
+        Arc::new(ReqwestFetcher::new(client)),
+        "key",
+        PlacesEndpoint::Text,
+    )
+    .base_url(server.uri());
+    let started = Instant::now();
+    let report = Scraper::builder()
+        .viewport(Viewport {
+            northeast: (24.95, 67.10),
+            southwest: (24.90, 67.00),
+        })
+        .query("biryani")
+        .grid(GridSpec {
+            rows: 1,
+            cols: 2,
+            ..GridSpec::default()
+        })
+        .rate_limit(RateLimit {
+            concurrency: 2,
+            requests_per_second: 1000.0,
+            burst: 10,
+        })
+        .retry(RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        })
+        .backend(Arc::new(backend))
+        .build()
+        .unwrap()
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(report.failed_requests, 2);
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[tokio::test]
+async fn compression_and_bind_address_follow_the_config() {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let compressed = build_client(&HttpConfig {
+        bind_address: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+        ..HttpConfig::default()
+    })
+    .unwrap();
+    compressed.get(server.uri()).send().await.unwrap();
+    let plain = build_client(&HttpConfig {
+        gzip: false,
+        brotli: false,
+        ..HttpConfig::default()
+    })
+    .unwrap();
+    plain.get(server.uri()).send().await.unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let encoding = requests[0].headers["accept-encoding"].to_str().unwrap();
+    assert!(encoding.contains("gzip") && encoding.contains("br"));
+    assert!(!requests[1].headers.contains_key("accept-encoding"));
+}
+
+#[test]
+fn ca_bundles_must_hold_certificates() {
+    let mut file = std::env::temp_dir();
+    file.push(format!("google_maps_ca_{}.pem", std::process::id()));
+    std::fs::File::create(&file)
+        .unwrap()
+        .write_all(b"not a certificate\n")
+        .unwrap();
+
+    assert!(matches!(
+        load_ca_bundle(&file),
+        Err(AppError::InvalidCaBundle { .. })
+    ));
+    let job = Job {
+        http: HttpConfig {
+            ca_bundle: Some(file.clone()),
+            ..HttpConfig::default()
+        },
+        ..Job::default()
+    };
+    assert!(matches!(job.validate(), Err(AppError::InvalidJob { .. })));
+    std::fs::remove_file(&file).unwrap();
+
+    assert!(matches!(
+        build_client(&HttpConfig {
+            ca_bundle: Some(file),
+            ..HttpConfig::default()
+        }),
+        Err(AppError::InvalidCaBundle { .. })
+    ));
+    let job = Job {
+        http: http(0),
+        ..Job::default()
+    };
+    assert!(matches!(job.validate(), Err(AppError::InvalidJob { .. })));
+}
@@ -0,0 +1,178 @@
+use google_maps::config::{GeocoderConfig, Job, JobFile};
+use google_maps::error::AppError;
+use google_maps::ScraperBuilder;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// Loading reads GOOGLE_MAPS_* variables, which the tests below set
+static ENV: Mutex<()> = Mutex::new(());
+
+const TOML_JOBS: &str = r#"
+[[jobs]]
+name = "karachi"
+area = "Karachi"
+queries = ["biryani", "nihari"]
+
+[jobs.grid]
+rows = 3
+cols = 4
+
+[jobs.rate_limit]
+concurrency = 2
+requests_per_second = 1.5
+burst = 3
+
+[[jobs]]
+name = "lahore"
+area = "Lahore"
+queries = ["karahi"]
+"#;
+
+const YAML_JOB: &str = r#"
+jobs:
+  - name: karachi
+    area: Karachi
+    queries: [biryani]
+    grid:
+      rows: 3
+      cols: 4
+    retry:
+      max_attempts: 5
+"#;
+
+fn write(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("google_maps_job_file_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn load(path: &Path) -> Result<JobFile, AppError> {
+    let _env = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    JobFile::load(path)
+}
+
+fn job(queries: &[&str]) -> Job {
+    Job {
+        name: "test".to_string(),
+        area: "Karachi".to_string(),
+        queries: queries.iter().map(|q| q.to_string()).collect(),
+        ..Job::default()
+    }
+}
+
+#[test]
+fn toml_and_yaml_files_are_loaded() {
+    let jobs = load(&write("jobs.toml", TOML_JOBS)).unwrap().jobs;
+    assert_eq!(jobs.len(), 2);
+    assert_eq!(jobs[0].queries, ["biryani", "nihari"]);
+    assert_eq!((jobs[0].grid.rows, jobs[0].grid.cols), (3, 4));
+    assert_eq!(jobs[0].rate_limit.requests_per_second, 1.5);
+    // Missing sections take their defaults
+    assert_eq!(jobs[1].max_pages, Job::default().max_pages);
+    assert_eq!((jobs[1].start, Job::default().start), (0, 0));
+    assert!(matches!(jobs[1].geocoder, GeocoderConfig::Google));
+
+    let yaml = load(&write("job.yaml", YAML_JOB)).unwrap().jobs;
+    assert_eq!(yaml[0].retry.max_attempts, 5);
+    assert_eq!(yaml[0].grid.cols, 4);
+
+    let example = Path::new(env!("CARGO_MANIFEST_DIR")).join("config/jobs.example.toml");
+    assert!(!load(&example).unwrap().jobs.is_empty());
+}
+
+#[test]
+fn malformed_files_are_rejected() {
+    assert!(matches!(
+        load(&write("jobs.json", "{}")),
+        Err(AppError::UnsupportedJobFile(_))
+    ));
+    assert!(matches!(
+        load(&write(
+            "unknown.toml",
+            "[[jobs]]\nqueries = [\"a\"]\ncolour = \"red\"\n"
+        )),
+        Err(AppError::TomlError(_))
+    ));
+    assert!(matches!(
+        load(&write("bad.yaml", "jobs:\n  - queries: 3\n")),
+        Err(AppError::YamlError(_))
+    ));
+    assert!(matches!(
+        load(&write("empty.toml", "jobs = []\n")),
+        Err(AppError::InvalidJob { .. })
+    ));
+}
+
+#[test]
+fn jobs_and_builders_share_their_validation() {
+    let mut invalid = vec![job(&[]), job(&["biryani", " "])];
+    let mut no_burst = job(&["biryani"]);
+    no_burst.rate_limit.burst = 0;
+    let mut jitter = job(&["biryani"]);
+    jitter.retry.jitter = 1.5;
+    let mut breaker = job(&["biryani"]);
+    breaker.circuit_breaker.min_requests = breaker.circuit_breaker.window + 1;
+    invalid.extend([no_burst, jitter, breaker]);
+
+    for job in &invalid {
+        match job.validate() {
+            Err(AppError::InvalidJob { job: name, reason }) => {
+                assert_eq!(name, "test");
+                // Reasons name the field without repeating the error kind
+                assert!(!reason.starts_with("Invalid"), "{}", reason);
+            }
+            other => panic!("{:?} accepted: {:?}", job.queries, other),
+        }
+        let built = ScraperBuilder::from_job(job).api_key("key").build();
+        assert!(matches!(built, Err(AppError::InvalidScraper(_))));
+    }
+
+    let mut no_area = job(&["biryani"]);
+    no_area.area.clear();
+    assert!(no_area.validate().is_err());
+    assert!(job(&["biryani"]).validate().is_ok());
+}
+
+#[test]
+fn environment_overrides_apply_to_single_job_files_only() {
+    let single = write(
+        "single.toml",
+        "[[jobs]]\narea = \"Karachi\"\nqueries = [\"biryani\"]\n",
+    );
+    let several = write("several.toml", TOML_JOBS);
+
+    let _env = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    std::env::set_var("GOOGLE_MAPS_AREA", "Lahore");
+    std::env::set_var("GOOGLE_MAPS_QUERIES", "karahi, haleem");
+    std::env::set_var("GOOGLE_MAPS_GRID_ROWS", "7");
+    let overridden = JobFile::load(&single);
+    let rejected = JobFile::load(&several);
+    std::env::set_var("GOOGLE_MAPS_GRID_ROWS", "many");
+    let malformed = JobFile::load(&single);
+    for name in [
+        "GOOGLE_MAPS_AREA",
+        "GOOGLE_MAPS_QUERIES",
+        "GOOGLE_MAPS_GRID_ROWS",
+    ] {
+        std::env::remove_var(name);
+    }
+    let unset = JobFile::load(&several);
+
+    let job = &overridden.unwrap().jobs[0];
+    assert_eq!(job.area, "Lahore");
+    assert_eq!(job.queries, ["karahi", "haleem"]);
+    assert_eq!(job.grid.rows, 7);
+
+    // Several jobs leave it unclear which one an override is meant for
+    assert!(matches!(
+        rejected,
+        Err(AppError::InvalidJob { reason, .. }) if reason.starts_with("GOOGLE_MAPS_AREA")
+    ));
+    assert_eq!(unset.unwrap().jobs[0].area, "Karachi");
+
+    assert!(matches!(malformed, Err(AppError::InvalidEnvVar { .. })));
+}
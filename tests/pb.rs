@@ -0,0 +1,203 @@
+use google_maps::config::Locale;
+use google_maps::error::AppError;
+use google_maps::models::Cell;
+use google_maps::services::google_maps::{build_url, MapView};
+use google_maps::services::pb::{PbBuilder, SearchPb, SessionTokens};
+use google_maps::services::pb_decoder::{diff_fields, parse_input, parse_pb, template_like};
+
+// The search URL as the hand-written `format!` template rendered it.
+const TEMPLATE_URL: &str = "https://www.google.com/search?tbm=map&authuser=0&hl=en&pb=!4m12!1m3!1d{}!2d{}!3d{}!2m3!1f0!2f0!3f0!3m2!1i{}!2i{}!4f{}!7i{}!10b1!12m21!1m2!18b1!30b1!2m3!5m1!6e2!20e3!10b1!12b1!13b1!16b1!17m1!3e1!20m3!5e2!6b1!14b1!46m1!1b0!94b1!96b1!19m4!2m3!1i360!2i120!4i8!20m57!2m2!1i203!2i100!3m2!2i4!5b1!6m6!1m2!1i86!2i86!1m2!1i408!2i240!7m42!1m3!1e1!2b0!3e3!1m3!1e2!2b1!3e2!1m3!1e2!2b0!3e3!1m3!1e8!2b0!3e3!1m3!1e10!2b0!3e3!1m3!1e10!2b1!3e2!1m3!1e9!2b1!3e2!1m3!1e10!2b0!3e3!1m3!1e10!2b1!3e2!1m3!1e10!2b0!3e4!2b1!4b1!9b0!22m5!1s1pk8Z7ObMvuRkdUPhKD9iAY%3A60!2s1i%3A0%2Ct%3A150715%2Cp%3A1pk8Z7ObMvuRkdUPhKD9iAY%3A60!7e81!12e3!17s1pk8Z7ObMvuRkdUPhKD9iAY%3A66!24m105!1m32!13m9!2b1!3b1!4b1!6i1!8b1!9b1!14b1!20b1!25b1!18m21!3b1!4b1!5b1!6b1!9b1!12b1!13b1!14b1!17b1!20b1!21b1!22b1!25b1!27m1!1b0!28b0!32b0!33m1!1b1!34b0!36e1!10m1!8e3!11m1!3e1!14m1!3b1!17b1!20m2!1e3!1e6!24b1!25b1!26b1!29b1!30m1!2b1!36b1!39m3!2m2!2i1!3i1!43b1!52b1!54m1!1b1!55b1!56m1!1b1!65m5!3m4!1m3!1m2!1i224!2i298!71b1!72m19!1m5!1b1!2b1!3b1!5b1!7b1!4b1!8m10!1m6!4m1!1e1!4m1!1e3!4m1!1e4!3sother_user_reviews!6m1!1e1!9b1!89b1!98m3!1b1!2b1!3b1!103b1!113b1!114m3!1b1!2m1!1b1!117b1!122m1!1b1!125b0!126b1!127b1!26m4!2m3!1i80!2i92!4i8!30m0!34m18!2b1!3b1!4b1!6b1!8m6!1b1!3b1!4b1!5b1!6b1!7b1!9b1!12b1!14b1!20b1!23b1!25b1!26b1!37m1!1e81!42b1!47m0!49m9!3b1!6m2!1b1!2b1!7m2!1e3!2b1!8b1!9b1!50m4!2e2!3m2!1b1!3b1!67m2!7b1!10b1!69i713&q={}&nfpr=1&tch=1&ech=1&psi=1pk8Z7ObMvuRkdUPhKD9iAY.1732024792533.1";
+
+fn template_url(lat: f64, lng: f64, view: &MapView, start: u32, query: &str) -> String {
+    [
+        view.span_m.to_string(),
+        lng.to_string(),
+        lat.to_string(),
+        view.width_px.to_string(),
+        view.height_px.to_string(),
+        view.zoom.to_string(),
+        start.to_string(),
+        query.to_string(),
+    ]
+    .iter()
+    .fold(TEMPLATE_URL.to_string(), |url, arg| {
+        url.replacen("{}", arg, 1)
+    })
+}
+
+#[test]
+fn build_url_reproduces_the_template() {
+    let zoomed = MapView {
+        span_m: 1687.4744643519907,
+        width_px: 512,
+        height_px: 621,
+        zoom: 16.1,
+    };
+    for (lat, lng, view, start) in [
+        (24.8607, 67.0011, MapView::DEFAULT, 0),
+        (-33.8688, 151.2093, MapView::DEFAULT, 40),
+        (51.5072, -0.1276, zoomed, 20),
+    ] {
+        assert_eq!(
+            build_url(lat, lng, &view, start, "restaurants", &Locale::default()),
+            template_url(lat, lng, &view, start, "restaurants")
+        );
+    }
+}
+
+#[test]
+fn preset_renders_session_tokens_encoded() {
+    let pb = SearchPb {
+        lat: 24.8607,
+        lng: 67.0011,
+        view: MapView::DEFAULT,
+        start: 0,
+        offset: None,
+        session: SessionTokens::captured(),
+    };
+    let encoded = pb.render_encoded();
+    assert!(encoded.starts_with("!4m12!1m3!1d13499.795714815926!2d67.0011!3d24.8607!"));
+    assert!(encoded.contains("!22m5!1s1pk8Z7ObMvuRkdUPhKD9iAY%3A60!"));
+    assert!(encoded.ends_with("!69i713"));
+}
+
+#[test]
+fn preset_adds_offset_after_field_seven() {
+    let pb = SearchPb {
+        lat: 0.0,
+        lng: 0.0,
+        view: MapView::DEFAULT,
+        start: 20,
+        offset: Some(40),
+        session: SessionTokens::captured(),
+    };
+    assert!(pb.builder().render().contains("!4f13.1!7i20!8i40!10b1!"));
+}
+
+#[test]
+fn message_counts_include_nested_fields() {
+    let pb = PbBuilder::new()
+        .message(1, |m| {
+            m.int(1, 5).message(2, |m| m.bool(1, true).bool(2, false))
+        })
+        .string(3, "a!b*c");
+    assert_eq!(pb.render(), "!1m4!1i5!2m2!1b1!2b0!3sa*21b*2Ac");
+}
+
+#[test]
+fn encoding_keeps_pb_separators() {
+    let pb = PbBuilder::new().string(1, "p:x,y z");
+    assert_eq!(pb.render_encoded(), "!1sp%3Ax%2Cy%20z");
+}
+
+#[test]
+fn decoder_round_trips_the_search_url() {
+    let url = build_url(
+        24.8607,
+        67.0011,
+        &MapView::DEFAULT,
+        20,
+        "pizza",
+        &Locale::default(),
+    );
+    let fields = parse_input(&url).unwrap();
+    let preset = SearchPb {
+        lat: 24.8607,
+        lng: 67.0011,
+        view: MapView::DEFAULT,
+        start: 20,
+        offset: None,
+        session: SessionTokens::captured(),
+    };
+    assert_eq!(fields, preset.builder().into_fields());
+    assert!(diff_fields(&fields, &template_like(&fields)).is_empty());
+}
+
+#[test]
+fn diff_reports_changed_added_and_removed_fields() {
+    let old = parse_pb("!1m2!1i5!2sa!3b1!3b0").unwrap();
+    let new = parse_pb("!1m1!1i6!3e1!4f1.5").unwrap();
+    let changes: Vec<String> = diff_fields(&old, &new)
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            "~ 1.1 i 5 -> i 6",
+            "- 1.2 s \"a\"",
+            "~ 3 b true -> e 1",
+            "- 3[1] b false",
+            "+ 4 f 1.5"
+        ]
+    );
+}
+
+#[test]
+fn decoder_rejects_truncated_messages() {
+    assert!(parse_pb("!1m3!1i2").is_err());
+    assert!(parse_input("https://www.google.com/search?q=pizza").is_err());
+}
+
+#[test]
+fn map_view_fits_the_cell() {
+    // About 1.1 km high and 2 km wide at this latitude
+    let cell = Cell::new(24.92, 67.05, 0.01, 0.02);
+    let view = MapView::for_cell(&cell).unwrap();
+    assert!((view.span_m - 1113.2).abs() < 1e-6);
+    assert_eq!((view.width_px, view.height_px), (1127, 621));
+    assert_eq!(view.zoom, 16.27);
+
+    let square = MapView::for_cell(&Cell::new(0.0, 0.0, 0.01, 0.01)).unwrap();
+    assert_eq!((square.width_px, square.height_px), (621, 621));
+    assert_eq!(square.zoom, 16.41);
+
+    // Screens stay within 100 and 4096 pixels wide however thin the cell
+    assert_eq!(
+        MapView::for_cell(&Cell::new(0.0, 0.0, 1.0, 0.01))
+            .unwrap()
+            .width_px,
+        100
+    );
+    // Positions are rendered longitude first, then latitude
+    let url = build_url(cell.lat, cell.lng, &view, 0, "biryani", &Locale::default());
+    assert!(url.contains("!2d67.05!3d24.92!"));
+    assert!(url.contains("!3m2!1i1127!2i621!4f16.27!7i0!"));
+}
+
+#[test]
+fn map_view_covers_cells_wider_than_the_widest_screen() {
+    // About 11 km wide and 111 m high, a 100:1 ratio
+    let cell = Cell::new(0.0, 0.0, 0.001, 0.1);
+    let view = MapView::for_cell(&cell).unwrap();
+    let (height_m, width_m) = cell.size_m();
+    assert_eq!((view.width_px, view.height_px), (4096, 621));
+
+    // The span grows past the cell height so the screen still holds its width
+    let metres_per_pixel = view.span_m / view.height_px as f64;
+    assert!(metres_per_pixel * view.width_px as f64 >= width_m - 1e-6);
+    assert!(view.span_m > height_m);
+    assert!(view.zoom.is_finite());
+
+    // Cells inside the screen limits are shown exactly
+    let cell = Cell::new(0.0, 0.0, 0.01, 0.05);
+    let view = MapView::for_cell(&cell).unwrap();
+    let (height_m, width_m) = cell.size_m();
+    assert!((view.span_m - height_m).abs() < 1e-6);
+    let metres_per_pixel = view.span_m / view.height_px as f64;
+    assert!(metres_per_pixel * view.width_px as f64 >= width_m - 1e-6);
+}
+
+#[test]
+fn map_view_rejects_cells_without_area() {
+    for cell in [
+        Cell::new(24.92, 67.05, 0.0, 0.01),
+        Cell::new(24.92, 67.05, 0.01, 0.0),
+        Cell::new(24.92, 67.05, f64::NAN, 0.01),
+    ] {
+        assert!(matches!(
+            MapView::for_cell(&cell),
+            Err(AppError::InvalidGrid(_))
+        ));
+    }
+}
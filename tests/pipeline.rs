@@ -0,0 +1,191 @@
+mod common;
+
+use common::{grid, maps_page, place, rate_limit, viewport};
+use google_maps::config::{GeocoderConfig, Job, OutputSink, RetryPolicy};
+use google_maps::utils::export::OutputFormat;
+use google_maps::utils::http_fetcher::FixtureFetcher;
+use google_maps::utils::json_utils::load_places;
+use google_maps::{run_with_fetcher, Scraper};
+
+use reqwest::StatusCode;
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use url::Url;
+
+// Whether a Maps search asks for the page starting at `offset`, which later
+// pages send in field 8 after the page size in field 7.
+fn page(url: &Url, offset: u32) -> bool {
+    let expected = match offset {
+        0 => "!7i20!10b1!".to_string(),
+        offset => format!("!7i20!8i{}!", offset),
+    };
+    url.path() == "/search"
+        && url
+            .query_pairs()
+            .any(|(name, pb)| name == "pb" && pb.contains(&expected))
+}
+
+fn output_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("google_maps_pipeline_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[tokio::test]
+async fn job_runs_from_grid_to_saved_files_without_network() {
+    let biryani = place("Student Biryani", "id-1", 24.92, 67.05);
+    let nihari = place("Javed Nihari", "id-2", 24.93, 67.04);
+    let karahi = place("Waheed Kabab", "id-3", 24.91, 67.06);
+    let fixture = Arc::new(
+        FixtureFetcher::new()
+            .respond(
+                |url| url.host_str() == Some("nominatim.invalid"),
+                StatusCode::OK,
+                json!([{ "boundingbox": ["24.90", "24.95", "67.00", "67.10"] }]).to_string(),
+            )
+            .respond(
+                |url| page(url, 0),
+                StatusCode::OK,
+                maps_page(&[biryani, nihari.clone()]),
+            )
+            .respond(
+                |url| page(url, 20),
+                StatusCode::OK,
+                maps_page(&[nihari, karahi]),
+            )
+            .respond_path("/search", maps_page(&[]))
+            .respond_path("/maps", "<script>kEI='fixtureEi'</script>"),
+    );
+
+    let dir = output_dir();
+    let (json_path, csv_path) = (dir.join("places.json"), dir.join("places.csv"));
+    let job = Job {
+        name: "offline".to_string(),
+        geocoder: GeocoderConfig::Nominatim {
+            url: "https://nominatim.invalid".to_string(),
+        },
+        queries: vec!["biryani".to_string()],
+        grid: grid(2, 2),
+        start: 0,
+        max_pages: 3,
+        rate_limit: rate_limit(2),
+        retry: RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        },
+        outputs: vec![
+            OutputSink {
+                path: json_path.clone(),
+                format: None,
+            },
+            OutputSink {
+                path: csv_path.clone(),
+                format: Some(OutputFormat::Csv),
+            },
+        ],
+        ..Job::default()
+    };
+    job.validate().unwrap();
+    run_with_fetcher(&job, fixture.clone()).await.unwrap();
+
+    let mut ids: Vec<String> = load_places(&json_path)
+        .unwrap()
+        .into_iter()
+        .filter_map(|place| place.place_id)
+        .collect();
+    ids.sort();
+    assert_eq!(ids, ["id-1", "id-2", "id-3"]);
+    assert_eq!(fs::read_to_string(&csv_path).unwrap().lines().count(), 4);
+
+    let requests = fixture.requests();
+    assert_eq!(requests[0].host_str(), Some("nominatim.invalid"));
+    // Sessions are bootstrapped from the fixture Maps page too
+    assert!(requests.iter().any(|url| url.path() == "/maps"));
+    let searches: Vec<&Url> = requests
+        .iter()
+        .filter(|url| url.host_str() == Some("www.google.com") && url.path() == "/search")
+        .collect();
+    assert!(searches.len() >= 4);
+    assert!(searches.iter().all(|url| {
+        url.query_pairs()
+            .any(|(name, q)| name == "q" && q == "biryani")
+            && url
+                .query_pairs()
+                .any(|(name, pb)| name == "pb" && pb.contains("fixtureEi"))
+    }));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn scraper_geocodes_through_text_search_without_network() {
+    let fixture = Arc::new(
+        FixtureFetcher::new()
+            .respond_path(
+                "/maps/api/place/textsearch/json",
+                json!({
+                    "status": "OK",
+                    "results": [{
+                        "name": "Karachi",
+                        "geometry": {
+                            "location": { "lat": 24.86, "lng": 67.0 },
+                            "viewport": {
+                                "northeast": { "lat": 24.95, "lng": 67.10 },
+                                "southwest": { "lat": 24.90, "lng": 67.00 }
+                            }
+                        }
+                    }]
+                })
+                .to_string(),
+            )
+            .respond_path(
+                "/search",
+                maps_page(&[place("Student Biryani", "id-1", 24.92, 67.05)]),
+            ),
+    );
+
+    let report = Scraper::builder()
+        .area("Karachi")
+        .api_key("test-key")
+        .query("biryani")
+        .grid(grid(1, 1))
+        .max_pages(1)
+        .rate_limit(rate_limit(2))
+        .fetcher(fixture.clone())
+        .build()
+        .unwrap()
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(report.places.len(), 1);
+    assert_eq!(report.places[0].name.as_deref(), Some("Student Biryani"));
+    let geocoding = &fixture.requests()[0];
+    assert!(geocoding
+        .query_pairs()
+        .any(|(name, value)| name == "key" && value == "test-key"));
+}
+
+#[tokio::test]
+async fn fixture_captcha_pages_count_as_blocked() {
+    let fixture = Arc::new(FixtureFetcher::new().respond_path(
+        "/search",
+        "<html><body><div class=\"g-recaptcha\"></div></body></html>",
+    ));
+
+    let report = Scraper::builder()
+        .viewport(viewport())
+        .query("biryani")
+        .grid(grid(1, 2))
+        .rate_limit(rate_limit(2))
+        .fetcher(fixture)
+        .build()
+        .unwrap()
+        .run()
+        .await
+        .unwrap();
+
+    assert!(report.places.is_empty());
+    assert_eq!(report.blocked_requests, 2);
+}
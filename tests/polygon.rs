@@ -0,0 +1,201 @@
+mod common;
+
+use common::{grid, place_with_id, rate_limit};
+use google_maps::config::AdaptiveConfig;
+use google_maps::error::AppError;
+use google_maps::models::{Cell, MultiPolygon};
+use google_maps::services::search_backend::{PageCursor, SearchBackend, SearchPage};
+use google_maps::Scraper;
+
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+// A square from 67.0 to 67.1 east and 24.9 to 25.0 north, with a hole in its middle
+const SQUARE_WITH_HOLE: &str = "POLYGON ((67.0 24.9, 67.1 24.9, 67.1 25.0, 67.0 25.0, 67.0 24.9), \
+     (67.04 24.94, 67.06 24.94, 67.06 24.96, 67.04 24.96, 67.04 24.94))";
+
+#[test]
+fn wkt_polygons_keep_their_holes() {
+    let area = MultiPolygon::from_wkt(SQUARE_WITH_HOLE).unwrap();
+    assert_eq!(area.polygons.len(), 1);
+    assert_eq!(area.polygons[0].exterior.len(), 5);
+    assert_eq!(area.polygons[0].holes.len(), 1);
+    // WKT positions are "lng lat", rings are stored as (lat, lng)
+    assert_eq!(area.polygons[0].exterior[1], (24.9, 67.1));
+
+    let viewport = area.viewport();
+    assert_eq!(viewport.southwest, (24.9, 67.0));
+    assert_eq!(viewport.northeast, (25.0, 67.1));
+
+    assert!(area.contains(24.92, 67.02));
+    assert!(!area.contains(24.95, 67.05));
+    assert!(!area.contains(25.05, 67.05));
+}
+
+#[test]
+fn wkt_multipolygons_hold_every_part() {
+    let area = MultiPolygon::from_wkt(
+        "multipolygon (((0 0, 1 0, 1 1, 0 1, 0 0)), ((5 5, 6 5, 6 6, 5 6, 5 5)))",
+    )
+    .unwrap();
+    assert_eq!(area.polygons.len(), 2);
+    assert!(area.contains(0.5, 0.5));
+    assert!(area.contains(5.5, 5.5));
+    assert!(!area.contains(3.0, 3.0));
+    assert_eq!(area.viewport().northeast, (6.0, 6.0));
+}
+
+#[test]
+fn malformed_wkt_is_rejected() {
+    for wkt in [
+        "POINT (67.0 24.9)",
+        "POLYGON 67.0 24.9",
+        "POLYGON ((67.0 24.9, 67.1 24.9, 67.1 25.0)",
+        "POLYGON ((67.0 24.9, 67.1 north))",
+        "POLYGON ((67.0 24.9, 67.1 24.9))",
+    ] {
+        assert!(
+            matches!(
+                MultiPolygon::from_wkt(wkt),
+                Err(AppError::InvalidPolygon(_))
+            ),
+            "{}",
+            wkt
+        );
+    }
+}
+
+#[test]
+fn geojson_features_and_multipolygons_are_read() {
+    let square = json!([
+        [
+            [67.0, 24.9],
+            [67.1, 24.9],
+            [67.1, 25.0],
+            [67.0, 25.0],
+            [67.0, 24.9]
+        ],
+        [
+            [67.04, 24.94],
+            [67.06, 24.94],
+            [67.06, 24.96],
+            [67.04, 24.96],
+            [67.04, 24.94]
+        ]
+    ]);
+    let collection = json!({
+        "type": "FeatureCollection",
+        "features": [
+            { "type": "Feature", "properties": {}, "geometry": { "type": "Polygon", "coordinates": square } },
+            { "type": "Feature", "properties": {}, "geometry": {
+                "type": "MultiPolygon",
+                "coordinates": [
+                    [[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]]],
+                    [[[5.0, 5.0], [6.0, 5.0], [6.0, 6.0], [5.0, 5.0]]]
+                ]
+            } },
+            { "type": "Feature", "properties": {}, "geometry": { "type": "Point", "coordinates": [3.0, 3.0] } },
+            { "type": "Feature", "properties": {}, "geometry": null }
+        ]
+    });
+
+    let area = MultiPolygon::from_geojson(&collection).unwrap();
+    assert_eq!(area.polygons.len(), 3);
+    assert_eq!(area.polygons[0].holes.len(), 1);
+    // GeoJSON positions are [lng, lat]
+    assert_eq!(area.polygons[0].exterior[1], (24.9, 67.1));
+    assert!(area.contains(24.92, 67.02));
+    assert!(!area.contains(24.95, 67.05));
+    assert!(area.contains(0.2, 0.8));
+    assert!(!area.contains(0.8, 0.2));
+
+    assert!(MultiPolygon::from_geojson(&json!({ "coordinates": [] })).is_err());
+    assert!(
+        MultiPolygon::from_geojson(&json!({ "type": "Point", "coordinates": [0, 0] })).is_err()
+    );
+    assert!(MultiPolygon::from_geojson(&json!({
+        "type": "Polygon",
+        "coordinates": [[["east", 24.9], [67.1, 24.9], [67.1, 25.0]]]
+    }))
+    .is_err());
+}
+
+#[test]
+fn cells_intersect_the_area_they_touch() {
+    let area = MultiPolygon::from_wkt(SQUARE_WITH_HOLE).unwrap();
+
+    // Inside, straddling an edge, and holding the whole area
+    assert!(area.intersects_cell(&Cell::new(24.92, 67.02, 0.01, 0.01)));
+    assert!(area.intersects_cell(&Cell::new(25.0, 67.05, 0.02, 0.02)));
+    assert!(area.intersects_cell(&Cell::new(24.95, 67.05, 1.0, 1.0)));
+    // Crossing the area without holding any of its vertices or having a corner in it
+    assert!(area.intersects_cell(&Cell::new(24.95, 67.05, 0.01, 0.5)));
+    // Outside, and inside the hole
+    assert!(!area.intersects_cell(&Cell::new(25.1, 67.05, 0.02, 0.02)));
+    assert!(!area.intersects_cell(&Cell::new(24.95, 67.05, 0.01, 0.01)));
+}
+
+// Returns one new place for every search, so each page is full, and records
+// the searched cells.
+#[derive(Debug, Default)]
+struct SaturatedBackend {
+    cells: Mutex<Vec<Cell>>,
+}
+
+#[async_trait]
+impl SearchBackend for SaturatedBackend {
+    async fn search(
+        &self,
+        cell: &Cell,
+        _query: &str,
+        _cursor: Option<&PageCursor>,
+    ) -> Result<SearchPage, AppError> {
+        let mut cells = self.cells.lock().unwrap();
+        cells.push(*cell);
+        Ok(SearchPage {
+            places: vec![place_with_id(&format!("place-{}", cells.len()))],
+            offset: 0,
+            next: None,
+        })
+    }
+
+    fn page_size(&self) -> usize {
+        1
+    }
+}
+
+#[tokio::test]
+async fn quadrants_outside_the_polygon_are_not_searched() {
+    // An L shape whose north-east quadrant is empty
+    let area = MultiPolygon::from_wkt(
+        "POLYGON ((67.00 24.90, 67.06 24.90, 67.06 24.91, 67.02 24.91, 67.02 24.93, 67.00 24.93, 67.00 24.90))",
+    )
+    .unwrap();
+    let backend = Arc::new(SaturatedBackend::default());
+
+    let report = Scraper::builder()
+        .polygon(area)
+        .query("biryani")
+        .grid(grid(1, 1))
+        .adaptive(Some(AdaptiveConfig {
+            max_depth: 1,
+            min_cell_size_m: 100.0,
+            ..AdaptiveConfig::default()
+        }))
+        .rate_limit(rate_limit(1))
+        .backend(backend.clone())
+        .build()
+        .unwrap()
+        .run()
+        .await
+        .unwrap();
+
+    let cells = backend.cells.lock().unwrap();
+    assert_eq!(cells.len(), 4);
+    assert_eq!(cells.iter().filter(|cell| cell.depth == 1).count(), 3);
+    assert!(!cells
+        .iter()
+        .any(|cell| cell.lat > 24.915 && cell.lng > 67.03));
+    assert_eq!(report.places.len(), 4);
+}
@@ -0,0 +1,23 @@
+use google_maps::utils::rate_limiter::RateLimiter;
+
+use futures::future::join_all;
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn burst_goes_out_at_once_then_the_rate_applies() {
+    let limiter = RateLimiter::new(20.0, 2);
+    let started = Instant::now();
+    limiter.acquire().await;
+    limiter.acquire().await;
+    assert!(started.elapsed() < Duration::from_millis(20));
+
+    // Four more tokens at 20 per second, shared by concurrent tasks
+    join_all((0..4).map(|_| {
+        let limiter = limiter.clone();
+        async move { limiter.acquire().await }
+    }))
+    .await;
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(190), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_millis(600), "{:?}", elapsed);
+}
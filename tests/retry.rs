@@ -0,0 +1,187 @@
+mod common;
+
+use google_maps::config::{PlacesEndpoint, RetryPolicy};
+use google_maps::error::AppError;
+use google_maps::services::places_api::PlacesBackend;
+use google_maps::utils::http_fetcher::ReqwestFetcher;
+use google_maps::utils::retry::with_retries;
+use google_maps::ScrapeReport;
+
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn fast_policy(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        initial_backoff_ms: 1,
+        max_backoff_ms: 10,
+        ..RetryPolicy::default()
+    }
+}
+
+async fn scrape(server: &MockServer, retry: RetryPolicy) -> ScrapeReport {
+    let backend = PlacesBackend::new(
+        Arc::new(ReqwestFetcher::default()),
+        "key",
+        PlacesEndpoint::Text,
+    )
+    .base_url(server.uri());
+    common::scrape(common::scraper(Arc::new(backend)).retry(retry)).await
+}
+
+fn ok_page() -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "status": "OK",
+        "results": [{
+            "name": "Student Biryani",
+            "place_id": "id-1",
+            "geometry": { "location": { "lat": 24.92, "lng": 67.05 } }
+        }]
+    }))
+}
+
+#[test]
+fn backoff_grows_exponentially_up_to_the_cap() {
+    let policy = RetryPolicy {
+        initial_backoff_ms: 100,
+        max_backoff_ms: 1000,
+        multiplier: 2.0,
+        jitter: 0.0,
+        ..RetryPolicy::default()
+    };
+    let delays: Vec<_> = (1..=6)
+        .map(|retry| policy.backoff(retry).as_millis())
+        .collect();
+    assert_eq!(delays, [100, 200, 400, 800, 1000, 1000]);
+
+    let jittered = RetryPolicy {
+        jitter: 0.5,
+        ..policy
+    };
+    for _ in 0..100 {
+        let delay = jittered.backoff(3).as_millis();
+        assert!((200..=400).contains(&delay), "{}", delay);
+    }
+}
+
+#[tokio::test]
+async fn server_errors_are_retried_and_reported() {
+    let server = MockServer::start().await;
+    Mock::given(path("/textsearch/json"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(2)
+        .mount(&server)
+        .await;
+    Mock::given(path("/textsearch/json"))
+        .respond_with(ok_page())
+        .mount(&server)
+        .await;
+
+    let report = scrape(&server, fast_policy(3)).await;
+    assert_eq!(report.places.len(), 1);
+    assert_eq!(
+        (report.requests, report.retries, report.failed_requests),
+        (3, 2, 0)
+    );
+    assert_eq!(report.plan[0].retries, 2);
+}
+
+#[tokio::test]
+async fn permanent_errors_are_not_retried() {
+    let server = MockServer::start().await;
+    Mock::given(path("/textsearch/json"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let report = scrape(&server, fast_policy(5)).await;
+    assert!(report.places.is_empty());
+    assert_eq!(
+        (report.requests, report.retries, report.failed_requests),
+        (1, 0, 1)
+    );
+}
+
+#[tokio::test]
+async fn exhausted_retries_count_as_a_failed_page() {
+    let server = MockServer::start().await;
+    Mock::given(path("/textsearch/json"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let report = scrape(&server, fast_policy(2)).await;
+    assert_eq!(
+        (report.requests, report.retries, report.failed_requests),
+        (2, 1, 1)
+    );
+}
+
+#[tokio::test]
+async fn retry_after_overrides_the_backoff() {
+    let server = MockServer::start().await;
+    Mock::given(path("/textsearch/json"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(path("/textsearch/json"))
+        .respond_with(ok_page())
+        .mount(&server)
+        .await;
+
+    let started = Instant::now();
+    let policy = RetryPolicy {
+        max_backoff_ms: 5_000,
+        ..fast_policy(2)
+    };
+    let report = scrape(&server, policy).await;
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    assert_eq!((report.places.len(), report.retries), (1, 1));
+}
+
+#[tokio::test]
+async fn retry_after_beyond_the_longest_backoff_is_not_retried() {
+    let started = Instant::now();
+    let mut attempts = 0;
+    let (result, retries) = with_retries(&fast_policy(3), "test", || {
+        attempts += 1;
+        async {
+            Err::<(), _>(AppError::HttpStatus {
+                status: 503,
+                retry_after: Some(Duration::from_secs(3600)),
+            })
+        }
+    })
+    .await;
+    assert!(matches!(
+        result,
+        Err(AppError::HttpStatus { status: 503, .. })
+    ));
+    assert_eq!((attempts, retries), (1, 0));
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn only_transient_errors_are_retryable() {
+    let mut attempts = 0;
+    let (result, retries) = with_retries(&fast_policy(4), "test", || {
+        attempts += 1;
+        async { Err::<(), _>(AppError::RequestDenied("bad key".to_string())) }
+    })
+    .await;
+    assert!(matches!(result, Err(AppError::RequestDenied(_))));
+    assert_eq!((attempts, retries), (1, 0));
+
+    let (result, retries) = with_retries(&fast_policy(4), "test", || async {
+        Err::<(), _>(AppError::OverQueryLimit(String::new()))
+    })
+    .await;
+    assert!(result.is_err());
+    assert_eq!(retries, 3);
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use common::{maps_page, place, scrape, scraper};
+use google_maps::config::{Locale, PlacesEndpoint};
+use google_maps::services::places_api::PlacesBackend;
+use google_maps::services::search_backend::ScrapingBackend;
+use google_maps::utils::http_fetcher::ReqwestFetcher;
+
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use wiremock::matchers::{method, path, query_param, query_param_is_missing};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn places_result(id: &str) -> Value {
+    json!({
+        "name": format!("Place {}", id),
+        "formatted_address": "Karachi, Pakistan",
+        "place_id": id,
+        "types": ["restaurant", "food"],
+        "rating": 4.5,
+        "user_ratings_total": 120,
+        "price_level": 2,
+        "geometry": { "location": { "lat": 24.92, "lng": 67.05 } }
+    })
+}
+
+#[tokio::test]
+async fn places_backend_follows_next_page_token() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/textsearch/json"))
+        .and(query_param("query", "biryani"))
+        .and(query_param("key", "test-key"))
+        .and(query_param_is_missing("pagetoken"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "status": "OK",
+            "results": [places_result("a"), places_result("b")],
+            "next_page_token": "page-2"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(path("/textsearch/json"))
+        .and(query_param("pagetoken", "page-2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "status": "OK",
+            "results": [places_result("b"), places_result("c")]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let backend = PlacesBackend::new(
+        Arc::new(ReqwestFetcher::default()),
+        "test-key",
+        PlacesEndpoint::Text,
+    )
+    .base_url(server.uri())
+    .token_delay(Duration::ZERO);
+    let report = scrape(scraper(Arc::new(backend))).await;
+
+    assert_eq!(report.requests, 2);
+    let ids: Vec<_> = report
+        .places
+        .iter()
+        .map(|p| (p.place_id.clone().unwrap(), p.page_offset))
+        .collect();
+    assert_eq!(
+        ids,
+        [
+            ("a".to_string(), Some(0)),
+            ("b".to_string(), Some(0)),
+            ("c".to_string(), Some(2))
+        ]
+    );
+    let place = &report.places[0];
+    assert_eq!(place.price_range.as_deref(), Some("$$"));
+    assert_eq!(place.reviews_count, Some(120));
+    assert_eq!(
+        place.address.street_address.as_deref(),
+        Some("Karachi, Pakistan")
+    );
+}
+
+#[tokio::test]
+async fn places_nearby_search_without_results_is_an_empty_page() {
+    let server = MockServer::start().await;
+    Mock::given(path("/nearbysearch/json"))
+        .and(query_param("keyword", "biryani"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "status": "ZERO_RESULTS",
+            "results": []
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let backend = PlacesBackend::new(
+        Arc::new(ReqwestFetcher::default()),
+        "key",
+        PlacesEndpoint::Nearby,
+    )
+    .base_url(server.uri());
+    let report = scrape(scraper(Arc::new(backend))).await;
+    assert!(report.places.is_empty());
+    assert_eq!((report.requests, report.failed_requests), (1, 0));
+}
+
+#[tokio::test]
+async fn scraping_backend_paginates_by_offset() {
+    let server = MockServer::start().await;
+    Mock::given(path("/search"))
+        .and(query_param("q", "biryani"))
+        .and(query_param("hl", "ur"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(maps_page(&[
+            place("Student Biryani", "id-1", 24.92, 67.05),
+            place("Nawab Biryani", "id-2", 24.92, 67.05),
+        ])))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let locale = Locale {
+        language: "ur".to_string(),
+        region: None,
+    };
+    let backend = ScrapingBackend::new(Arc::new(ReqwestFetcher::default()), locale, 0)
+        .unwrap()
+        .base_url(format!("{}/search", server.uri()));
+    let report = scrape(scraper(Arc::new(backend))).await;
+
+    // The second page repeats the first, so pagination stops there
+    assert_eq!(report.requests, 2);
+    let names: Vec<_> = report
+        .places
+        .iter()
+        .filter_map(|p| p.name.clone())
+        .collect();
+    assert_eq!(names, ["Student Biryani", "Nawab Biryani"]);
+    assert_eq!(report.places[0].coordinates.lat, Some(24.92));
+}
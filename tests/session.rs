@@ -0,0 +1,289 @@
+use google_maps::config::{BrowserConfig, Locale, ProxyRotation};
+use google_maps::models::Cell;
+use google_maps::services::search_backend::{ScrapingBackend, SearchBackend};
+use google_maps::services::session::SessionPool;
+use google_maps::utils::http_fetcher::{HttpFetcher, ReqwestFetcher};
+use google_maps::utils::proxy_pool::{ProxyLease, ProxyPool};
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use url::Url;
+use wiremock::matchers::{body_string_contains, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const EI: &str = "Zk3aZ9XhLp2hkdUPq_6xAQ";
+
+// Page of the consent wall, answered by the form rejecting optional cookies
+const CONSENT_PAGE: &str = r#"<!doctype html><html><body>
+<a href="https://consent.google.com/info">Before you continue</a>
+<form action="/save" method="POST"><input type="hidden" name="set_eom" value="false"><input type="submit" value="Accept all"></form>
+<form action="/save" method="POST"><input type="hidden" name="set_eom" value="true"><input type="hidden" name="continue" value="/maps?hl=en&amp;gl=pk"><input type="submit" value="Reject all"></form>
+</body></html>"#;
+
+fn maps_page() -> String {
+    format!(
+        "<!doctype html><html><script>window.google={{kEI='{}',kEXPI='0'}}</script></html>",
+        EI
+    )
+}
+
+fn config(session_requests: u32, session_ttl_secs: u64) -> BrowserConfig {
+    BrowserConfig {
+        profiles: vec!["chrome".to_string()],
+        session_requests,
+        session_ttl_secs,
+        ..BrowserConfig::default()
+    }
+}
+
+fn landing(server: &MockServer) -> Url {
+    Url::parse(&format!("{}/maps", server.uri())).unwrap()
+}
+
+async fn consent_server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/maps"))
+        .and(header("cookie", "SOCS=rejected"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(maps_page()))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/maps"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(CONSENT_PAGE))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/save"))
+        .and(body_string_contains("set_eom=true"))
+        .respond_with(
+            ResponseTemplate::new(303)
+                .insert_header("set-cookie", "SOCS=rejected; Path=/")
+                .insert_header("location", "/maps?hl=en"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(path("/search"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(")]}'\n[]"))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn bootstrap_answers_the_consent_page_and_sends_its_cookies() {
+    let fetcher: Arc<dyn HttpFetcher> = Arc::new(ReqwestFetcher::default());
+    let direct = ProxyLease::direct(Arc::clone(&fetcher));
+    let server = consent_server().await;
+    let sessions = SessionPool::new(&config(50, 1800), &Locale::default())
+        .unwrap()
+        .landing_url(landing(&server));
+
+    let session = sessions.session(&direct).await;
+    assert_eq!(session.tokens.ei, EI);
+    assert_eq!(session.headers["cookie"], "SOCS=rejected");
+
+    let backend = ScrapingBackend::new(Arc::clone(&fetcher), Locale::default(), 0)
+        .unwrap()
+        .base_url(format!("{}/search", server.uri()))
+        .sessions(Arc::new(sessions));
+    let _ = backend
+        .search(&Cell::new(24.92, 67.05, 0.01, 0.01), "biryani", None)
+        .await;
+
+    let requests = server.received_requests().await.unwrap();
+    let search = requests
+        .iter()
+        .find(|request| request.url.path() == "/search")
+        .unwrap();
+    assert_eq!(search.headers["cookie"], "SOCS=rejected");
+    let pb = search
+        .url
+        .query_pairs()
+        .find(|(name, _)| name == "pb")
+        .unwrap()
+        .1
+        .into_owned();
+    assert!(pb.contains(EI));
+}
+
+#[tokio::test]
+async fn sessions_are_renewed_when_they_expire() {
+    let fetcher: Arc<dyn HttpFetcher> = Arc::new(ReqwestFetcher::default());
+    let direct = ProxyLease::direct(Arc::clone(&fetcher));
+    let server = consent_server().await;
+    let pool = SessionPool::new(&config(2, 1800), &Locale::default())
+        .unwrap()
+        .landing_url(landing(&server));
+    let first = pool.session(&direct).await;
+    assert!(Arc::ptr_eq(&first, &pool.session(&direct).await));
+    assert!(!Arc::ptr_eq(&first, &pool.session(&direct).await));
+
+    let pool = SessionPool::new(&config(50, 1), &Locale::default())
+        .unwrap()
+        .landing_url(landing(&server));
+    let first = pool.session(&direct).await;
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert!(!Arc::ptr_eq(&first, &pool.session(&direct).await));
+
+    let pool = SessionPool::new(&config(50, 1800), &Locale::default())
+        .unwrap()
+        .landing_url(landing(&server));
+    let first = pool.session(&direct).await;
+    pool.invalidate(&first).await;
+    let second = pool.session(&direct).await;
+    assert!(!Arc::ptr_eq(&first, &second));
+    // Invalidating a session that was already replaced keeps the current one
+    pool.invalidate(&first).await;
+    assert!(Arc::ptr_eq(&second, &pool.session(&direct).await));
+}
+
+#[tokio::test]
+async fn captcha_pages_drop_the_session() {
+    let fetcher: Arc<dyn HttpFetcher> = Arc::new(ReqwestFetcher::default());
+    let direct = ProxyLease::direct(Arc::clone(&fetcher));
+    let server = consent_server().await;
+    Mock::given(path("/search"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string("<html><div class=\"g-recaptcha\"></div></html>"),
+        )
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    let sessions = Arc::new(
+        SessionPool::new(&config(50, 1800), &Locale::default())
+            .unwrap()
+            .landing_url(landing(&server)),
+    );
+    let backend = ScrapingBackend::new(Arc::clone(&fetcher), Locale::default(), 0)
+        .unwrap()
+        .base_url(format!("{}/search", server.uri()))
+        .sessions(Arc::clone(&sessions));
+
+    let before = sessions.session(&direct).await;
+    assert!(backend
+        .search(&Cell::new(24.92, 67.05, 0.01, 0.01), "biryani", None)
+        .await
+        .is_err());
+    assert!(!Arc::ptr_eq(&before, &sessions.session(&direct).await));
+}
+
+#[tokio::test]
+async fn failed_bootstraps_fall_back_to_generated_tokens() {
+    let fetcher: Arc<dyn HttpFetcher> = Arc::new(ReqwestFetcher::default());
+    let direct = ProxyLease::direct(Arc::clone(&fetcher));
+    let server = MockServer::start().await;
+    Mock::given(path("/maps"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<html>nothing here</html>"))
+        .mount(&server)
+        .await;
+
+    let pool = SessionPool::new(&config(50, 1800), &Locale::default())
+        .unwrap()
+        .landing_url(landing(&server));
+    let session = pool.session(&direct).await;
+    assert_eq!(session.tokens.ei.len(), 23);
+    assert!(!session.headers.contains_key("cookie"));
+}
+
+// Proxy answering for `http://maps.invalid` with a Maps page of its own `ei`
+async fn maps_proxy(ei: &str) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(path("/maps"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("set-cookie", format!("NID={}; Path=/", ei))
+                .set_body_string(format!("<html><script>kEI='{}'</script></html>", ei)),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(path("/search"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(")]}'\n[]"))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn sessions_are_bootstrapped_through_their_proxy() {
+    let fetcher: Arc<dyn HttpFetcher> = Arc::new(ReqwestFetcher::default());
+    let proxies = [
+        maps_proxy("firstProxyEi").await,
+        maps_proxy("secondProxyEi").await,
+    ];
+    let pool = ProxyPool::new(
+        &[proxies[0].uri(), proxies[1].uri()],
+        ProxyRotation::Request,
+        3,
+        fetcher.as_ref(),
+    )
+    .unwrap();
+    let sessions = SessionPool::new(&config(50, 1800), &Locale::default())
+        .unwrap()
+        .landing_url(Url::parse("http://maps.invalid/maps").unwrap());
+    let backend = ScrapingBackend::new(Arc::clone(&fetcher), Locale::default(), 0)
+        .unwrap()
+        .base_url("http://maps.invalid/search")
+        .proxies(Some(Arc::new(pool)))
+        .sessions(Arc::new(sessions));
+
+    for _ in 0..4 {
+        let _ = backend
+            .search(&Cell::new(24.92, 67.05, 0.01, 0.01), "biryani", None)
+            .await;
+    }
+
+    for (proxy, ei) in proxies.iter().zip(["firstProxyEi", "secondProxyEi"]) {
+        let requests = proxy.received_requests().await.unwrap();
+        let (bootstraps, searches): (Vec<_>, Vec<_>) = requests
+            .iter()
+            .partition(|request| request.url.path() == "/maps");
+        assert_eq!((bootstraps.len(), searches.len()), (1, 2));
+        for search in searches {
+            assert_eq!(search.headers["cookie"], format!("NID={}", ei).as_str());
+            assert!(search
+                .url
+                .query_pairs()
+                .any(|(name, pb)| name == "pb" && pb.contains(ei)));
+        }
+    }
+}
+
+#[tokio::test]
+async fn a_slow_bootstrap_only_holds_up_its_own_proxy() {
+    let fetcher: Arc<dyn HttpFetcher> = Arc::new(ReqwestFetcher::default());
+    let slow = MockServer::start().await;
+    Mock::given(path("/maps"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string("<html><script>kEI='slowProxyEi'</script></html>")
+                .set_delay(Duration::from_secs(2)),
+        )
+        .mount(&slow)
+        .await;
+    let fast = maps_proxy("fastProxyEi").await;
+    let pool = ProxyPool::new(
+        &[slow.uri(), fast.uri()],
+        ProxyRotation::Request,
+        3,
+        fetcher.as_ref(),
+    )
+    .unwrap();
+    let cell = Cell::new(24.92, 67.05, 0.01, 0.01);
+    let (first, second) = (pool.lease(&cell).unwrap(), pool.lease(&cell).unwrap());
+    let sessions = SessionPool::new(&config(50, 1800), &Locale::default())
+        .unwrap()
+        .landing_url(Url::parse("http://maps.invalid/maps").unwrap());
+
+    let started = Instant::now();
+    let (slow_session, fast_session) = tokio::join!(sessions.session(&first), async {
+        let session = sessions.session(&second).await;
+        (session, started.elapsed())
+    });
+    let (fast_session, fast_elapsed) = fast_session;
+    assert_eq!(fast_session.tokens.ei, "fastProxyEi");
+    assert!(fast_elapsed < Duration::from_secs(1), "{:?}", fast_elapsed);
+    assert_eq!(slow_session.tokens.ei, "slowProxyEi");
+}
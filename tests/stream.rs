@@ -0,0 +1,95 @@
+mod common;
+
+use common::{grid, place_with_id};
+use google_maps::error::AppError;
+use google_maps::models::{Cell, Place};
+use google_maps::services::search_backend::{PageCursor, SearchBackend, SearchPage};
+use google_maps::Scraper;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+const PLACES_PER_SEARCH: usize = 3;
+
+// Answers every search with new places and counts the searches.
+#[derive(Debug, Default)]
+struct CountingBackend {
+    searches: AtomicUsize,
+}
+
+#[async_trait]
+impl SearchBackend for CountingBackend {
+    async fn search(
+        &self,
+        _cell: &Cell,
+        _query: &str,
+        _cursor: Option<&PageCursor>,
+    ) -> Result<SearchPage, AppError> {
+        let search = self.searches.fetch_add(1, Ordering::SeqCst);
+        let places = (0..PLACES_PER_SEARCH)
+            .map(|i| place_with_id(&format!("place-{}-{}", search, i)))
+            .collect();
+        Ok(SearchPage {
+            places,
+            offset: 0,
+            next: None,
+        })
+    }
+
+    fn page_size(&self) -> usize {
+        20
+    }
+}
+
+fn scraper(backend: Arc<CountingBackend>, cols: usize) -> Scraper {
+    common::scraper(backend)
+        .grid(grid(1, cols))
+        .max_pages(1)
+        .buffer(1)
+        .build()
+        .unwrap()
+}
+
+async fn settle() {
+    tokio::time::sleep(Duration::from_millis(100)).await;
+}
+
+#[tokio::test]
+async fn fetching_waits_for_a_slow_consumer() {
+    let backend = Arc::new(CountingBackend::default());
+    let scraper = scraper(Arc::clone(&backend), 4);
+
+    let stream = scraper.stream().await.unwrap();
+    settle().await;
+    // The first page fills the buffer, so its worker waits before the next search
+    assert_eq!(backend.searches.load(Ordering::SeqCst), 1);
+
+    let places: Vec<Place> = stream.map(Result::unwrap).collect().await;
+    assert_eq!(places.len(), 4 * PLACES_PER_SEARCH);
+    assert_eq!(backend.searches.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn dropping_the_stream_stops_the_workers() {
+    let backend = Arc::new(CountingBackend::default());
+    let scraper = scraper(Arc::clone(&backend), 50);
+
+    let mut stream = Box::pin(scraper.stream().await.unwrap());
+    for _ in 0..PLACES_PER_SEARCH + 1 {
+        stream.next().await.unwrap().unwrap();
+    }
+    drop(stream);
+    settle().await;
+    let searches = backend.searches.load(Ordering::SeqCst);
+    settle().await;
+
+    assert_eq!(backend.searches.load(Ordering::SeqCst), searches);
+    assert!(
+        searches < 5,
+        "{} searches after the stream was dropped",
+        searches
+    );
+}
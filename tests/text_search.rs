@@ -0,0 +1,83 @@
+use google_maps::config::RetryPolicy;
+use google_maps::error::AppError;
+use google_maps::models::TextSearchResponse;
+use google_maps::services::google_maps::search_location;
+use google_maps::utils::http_fetcher::FixtureFetcher;
+
+use serde_json::json;
+
+fn response(status: &str) -> TextSearchResponse {
+    serde_json::from_value(json!({
+        "status": status,
+        "results": [],
+        "error_message": "details"
+    }))
+    .unwrap()
+}
+
+#[test]
+fn statuses_map_to_distinct_errors() {
+    let check = |status| response(status).check_status("Karachi").unwrap_err();
+    assert!(matches!(check("ZERO_RESULTS"), AppError::ZeroResults(q) if q == "Karachi"));
+    assert!(matches!(check("OVER_QUERY_LIMIT"), AppError::OverQueryLimit(m) if m == "details"));
+    assert!(matches!(
+        check("REQUEST_DENIED"),
+        AppError::RequestDenied(_)
+    ));
+    assert!(matches!(
+        check("INVALID_REQUEST"),
+        AppError::InvalidRequest(_)
+    ));
+    assert!(matches!(
+        check("UNKNOWN_ERROR"),
+        AppError::PlacesServerError(_)
+    ));
+    assert!(matches!(check("NOT_FOUND"), AppError::UnexpectedStatus(s) if s == "NOT_FOUND"));
+}
+
+#[test]
+fn ok_responses_expose_the_first_viewport() {
+    let response: TextSearchResponse = serde_json::from_value(json!({
+        "status": "OK",
+        "next_page_token": "token",
+        "results": [{
+            "name": "Karachi",
+            "formatted_address": "Karachi, Pakistan",
+            "place_id": "ChIJv0sdZQY-sz4RIwxaVUQv-Zw",
+            "geometry": {
+                "location": { "lat": 24.86, "lng": 67.0 },
+                "viewport": {
+                    "northeast": { "lat": 25.2, "lng": 67.5 },
+                    "southwest": { "lat": 24.7, "lng": 66.6 }
+                }
+            }
+        }]
+    }))
+    .unwrap();
+    let response = response.check_status("Karachi").unwrap();
+    assert_eq!(response.next_page_token.as_deref(), Some("token"));
+    let viewport = response.viewport().unwrap();
+    assert_eq!(viewport.northeast, (25.2, 67.5));
+    assert_eq!(viewport.southwest, (24.7, 66.6));
+}
+
+#[tokio::test]
+async fn text_search_retries_follow_the_given_policy() {
+    for max_attempts in [1, 3] {
+        let fixture = FixtureFetcher::new().respond_path(
+            "/maps/api/place/textsearch/json",
+            json!({ "status": "UNKNOWN_ERROR", "results": [] }).to_string(),
+        );
+        let retry = RetryPolicy {
+            max_attempts,
+            initial_backoff_ms: 1,
+            ..RetryPolicy::default()
+        };
+
+        let error = search_location(&fixture, "key", "Karachi", &retry)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, AppError::PlacesServerError(_)));
+        assert_eq!(fixture.requests().len(), max_attempts as usize);
+    }
+}
@@ -0,0 +1,59 @@
+use google_maps::config::Locale;
+use google_maps::services::google_maps::{build_url, text_search_url, MapView};
+use google_maps::services::pb_decoder::parse_input;
+
+use proptest::prelude::*;
+use url::Url;
+
+fn param(url: &Url, name: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+}
+
+proptest! {
+    #[test]
+    fn search_query_round_trips(query in "\\PC*", language in "[a-z]{2}(-[A-Z]{2})?") {
+        let locale = Locale { language: language.clone(), region: None };
+        let url = Url::parse(&build_url(24.8607, 67.0011, &MapView::DEFAULT, 0, &query, &locale)).unwrap();
+        prop_assert_eq!(param(&url, "q"), Some(query));
+        prop_assert_eq!(param(&url, "hl"), Some(language));
+        prop_assert_eq!(param(&url, "gl"), None);
+        prop_assert_eq!(url.query_pairs().count(), 9);
+    }
+
+    #[test]
+    fn region_round_trips(query in any::<String>(), region in any::<String>()) {
+        let locale = Locale { language: "ur".to_string(), region: Some(region.clone()) };
+        let url = Url::parse(&build_url(0.0, 0.0, &MapView::DEFAULT, 20, &query, &locale)).unwrap();
+        prop_assert_eq!(param(&url, "q"), Some(query));
+        prop_assert_eq!(param(&url, "gl"), Some(region));
+        prop_assert!(parse_input(url.as_str()).is_ok());
+    }
+
+    #[test]
+    fn text_search_parameters_round_trip(query in any::<String>(), key in any::<String>()) {
+        let url = text_search_url(&key, &query);
+        prop_assert_eq!(param(&url, "query"), Some(query));
+        prop_assert_eq!(param(&url, "key"), Some(key));
+        prop_assert_eq!(url.query_pairs().count(), 2);
+    }
+}
+
+#[test]
+fn reserved_and_non_ascii_characters_are_encoded() {
+    let locale = Locale {
+        language: "ur".to_string(),
+        region: Some("pk".to_string()),
+    };
+    let url = build_url(
+        24.8607,
+        67.0011,
+        &MapView::DEFAULT,
+        0,
+        "biryani & karahi #1 کراچی",
+        &locale,
+    );
+    assert!(url.contains("&hl=ur&gl=pk&pb=!4m12!"));
+    assert!(url.contains("&q=biryani+%26+karahi+%231+%DA%A9%D8%B1%D8%A7%DA%86%DB%8C&nfpr=1"));
+}
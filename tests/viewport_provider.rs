@@ -0,0 +1,103 @@
+use google_maps::config::{GeocoderConfig, RetryPolicy};
+use google_maps::models::Viewport;
+use google_maps::services::viewport_provider::{from_config, Nominatim, ViewportProvider};
+use google_maps::utils::http_fetcher::ReqwestFetcher;
+
+use serde_json::json;
+use std::io::Write;
+use std::sync::Arc;
+use wiremock::matchers::{header_exists, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn nominatim_reads_the_first_bounding_box() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/search"))
+        .and(query_param("q", "Karachi, Pakistan"))
+        .and(query_param("format", "jsonv2"))
+        .and(header_exists("user-agent"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {
+                "display_name": "Karachi",
+                "boundingbox": ["24.7", "25.2", "66.6", "67.6"]
+            },
+            {
+                "display_name": "Karachi Division",
+                "boundingbox": ["0", "1", "0", "1"]
+            }
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = Nominatim::new(Arc::new(ReqwestFetcher::default()), server.uri());
+    let viewport = provider.viewport("Karachi, Pakistan").await.unwrap();
+    assert_eq!(viewport.southwest, (24.7, 66.6));
+    assert_eq!(viewport.northeast, (25.2, 67.6));
+}
+
+#[tokio::test]
+async fn nominatim_without_results_has_no_viewport() {
+    let server = MockServer::start().await;
+    Mock::given(path("/search"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .mount(&server)
+        .await;
+
+    let provider = Nominatim::new(
+        Arc::new(ReqwestFetcher::default()),
+        format!("{}/", server.uri()),
+    );
+    assert!(provider.viewport("Atlantis").await.is_err());
+}
+
+#[tokio::test]
+async fn keyless_providers_ignore_the_area_name() {
+    let bbox = GeocoderConfig::Bbox {
+        south: 24.7,
+        west: 66.9,
+        north: 25.1,
+        east: 67.3,
+    };
+    let viewport = from_config(
+        &bbox,
+        Arc::new(ReqwestFetcher::default()),
+        &RetryPolicy::default(),
+    )
+    .unwrap()
+    .viewport("ignored")
+    .await
+    .unwrap();
+    assert_eq!(viewport.northeast, (25.1, 67.3));
+
+    let mut file = tempfile();
+    write!(
+        file.1,
+        "POLYGON ((67.0 24.8, 67.2 24.8, 67.1 25.0, 67.0 24.8))"
+    )
+    .unwrap();
+    let polygon = GeocoderConfig::Polygon {
+        path: file.0.clone(),
+    };
+    let Viewport {
+        northeast,
+        southwest,
+    } = from_config(
+        &polygon,
+        Arc::new(ReqwestFetcher::default()),
+        &RetryPolicy::default(),
+    )
+    .unwrap()
+    .viewport("")
+    .await
+    .unwrap();
+    assert_eq!((southwest, northeast), ((24.8, 67.0), (25.0, 67.2)));
+    std::fs::remove_file(&file.0).unwrap();
+}
+
+fn tempfile() -> (std::path::PathBuf, std::fs::File) {
+    let path = std::env::temp_dir().join(format!("boundary-{}.wkt", std::process::id()));
+    let file = std::fs::File::create(&path).unwrap();
+    (path, file)
+}